@@ -0,0 +1,6 @@
+CREATE TABLE processed_payment_events (
+    event_id TEXT PRIMARY KEY,
+    order_id UUID NOT NULL,
+    kind TEXT NOT NULL,
+    processed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
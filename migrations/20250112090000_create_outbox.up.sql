@@ -0,0 +1,13 @@
+CREATE TABLE outbox (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    username TEXT NOT NULL,
+    status session_status NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMP
+);
+
+CREATE INDEX outbox_pending_idx ON outbox (next_attempt_at) WHERE delivered_at IS NULL;
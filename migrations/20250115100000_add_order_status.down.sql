@@ -0,0 +1,26 @@
+CREATE TYPE session_status AS ENUM ('open','complete','expired');
+
+ALTER TABLE outbox ALTER COLUMN status TYPE session_status USING (CASE status
+    WHEN 'pending' THEN 'open'
+    WHEN 'awaiting_payment' THEN 'open'
+    WHEN 'paid' THEN 'complete'
+    WHEN 'fulfilled' THEN 'complete'
+    WHEN 'refunded' THEN 'complete'
+    ELSE 'expired'
+END)::session_status;
+
+ALTER TABLE order_details ADD COLUMN status session_status;
+
+UPDATE order_details
+SET status = CASE order_status
+    WHEN 'pending' THEN NULL
+    WHEN 'awaiting_payment' THEN 'open'
+    WHEN 'paid' THEN 'complete'
+    WHEN 'fulfilled' THEN 'complete'
+    WHEN 'refunded' THEN 'complete'
+    ELSE 'expired'
+END::session_status;
+
+ALTER TABLE order_details DROP COLUMN order_status;
+
+DROP TYPE order_status;
@@ -0,0 +1,29 @@
+CREATE TYPE order_status AS ENUM (
+    'pending',
+    'awaiting_payment',
+    'paid',
+    'fulfilled',
+    'cancelled',
+    'expired',
+    'refunded'
+);
+
+ALTER TABLE order_details ADD COLUMN order_status order_status NOT NULL DEFAULT 'pending';
+
+UPDATE order_details
+SET order_status = CASE status
+    WHEN 'open' THEN 'awaiting_payment'
+    WHEN 'complete' THEN 'paid'
+    WHEN 'expired' THEN 'expired'
+    ELSE 'pending'
+END::order_status;
+
+ALTER TABLE order_details DROP COLUMN status;
+
+ALTER TABLE outbox ALTER COLUMN status TYPE order_status USING (CASE status
+    WHEN 'open' THEN 'awaiting_payment'
+    WHEN 'complete' THEN 'paid'
+    ELSE 'expired'
+END)::order_status;
+
+DROP TYPE session_status;
@@ -0,0 +1,15 @@
+-- no foreign key on order_id, the history outlives deleted orders
+CREATE TABLE order_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    order_id UUID NOT NULL,
+    previous_status order_status,
+    new_status order_status,
+    actor TEXT NOT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX order_status_history_order_idx ON order_status_history (order_id, changed_at);
+
+INSERT INTO order_status_history (order_id, previous_status, new_status, actor, changed_at)
+SELECT id, NULL, order_status, username, created_at
+FROM order_details;
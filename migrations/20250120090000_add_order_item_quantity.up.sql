@@ -0,0 +1,2 @@
+ALTER TABLE order_item
+    ADD COLUMN quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity > 0);
@@ -0,0 +1,4 @@
+ALTER TABLE order_item DROP COLUMN currency;
+ALTER TABLE order_details DROP COLUMN currency;
+
+DROP TYPE currency;
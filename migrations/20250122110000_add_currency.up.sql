@@ -0,0 +1,4 @@
+CREATE TYPE currency AS ENUM ('EUR', 'USD', 'GBP', 'CHF', 'JPY');
+
+ALTER TABLE order_details ADD COLUMN currency currency NOT NULL DEFAULT 'EUR';
+ALTER TABLE order_item ADD COLUMN currency currency NOT NULL DEFAULT 'EUR';
@@ -0,0 +1,12 @@
+CREATE TABLE products (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE TABLE product_prices (
+    product_id UUID NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    currency currency NOT NULL,
+    price NUMERIC(10,2) NOT NULL CHECK (price > 0),
+    PRIMARY KEY (product_id, currency)
+);
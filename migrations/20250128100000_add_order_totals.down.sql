@@ -0,0 +1,5 @@
+ALTER TABLE order_details
+    DROP COLUMN total,
+    DROP COLUMN shipping,
+    DROP COLUMN tax,
+    DROP COLUMN subtotal;
@@ -0,0 +1,23 @@
+ALTER TABLE order_details
+    ADD COLUMN subtotal NUMERIC(12, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN tax NUMERIC(12, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN shipping NUMERIC(12, 2) NOT NULL DEFAULT 0,
+    ADD COLUMN total NUMERIC(12, 2) NOT NULL DEFAULT 0;
+
+-- Existing orders were charged without shipping, their tax is the 19% VAT included in the prices.
+UPDATE order_details d
+SET subtotal = i.subtotal,
+    total = i.subtotal,
+    tax = ROUND(i.subtotal * 0.19 / 1.19, CASE WHEN d.currency = 'JPY' THEN 0 ELSE 2 END)
+FROM (
+    SELECT order_id, SUM(price * quantity) AS subtotal
+    FROM order_item
+    GROUP BY order_id
+) i
+WHERE i.order_id = d.id;
+
+ALTER TABLE order_details
+    ALTER COLUMN subtotal DROP DEFAULT,
+    ALTER COLUMN tax DROP DEFAULT,
+    ALTER COLUMN shipping DROP DEFAULT,
+    ALTER COLUMN total DROP DEFAULT;
@@ -0,0 +1,8 @@
+-- Order events can't be sent in the old format.
+DELETE FROM outbox;
+
+ALTER TABLE outbox
+    DROP COLUMN event_type,
+    DROP COLUMN payload,
+    ADD COLUMN username TEXT NOT NULL,
+    ADD COLUMN status order_status NOT NULL;
@@ -0,0 +1,16 @@
+-- Pending checkout notifications only carry username and status and can't be
+-- turned into order events, they are retired instead of being sent in the new format.
+UPDATE outbox
+SET delivered_at = NOW(),
+    last_error = 'superseded by versioned order events'
+WHERE delivered_at IS NULL;
+
+ALTER TABLE outbox
+    DROP COLUMN username,
+    DROP COLUMN status,
+    ADD COLUMN event_type TEXT NOT NULL DEFAULT 'CheckoutResult',
+    ADD COLUMN payload JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE outbox
+    ALTER COLUMN event_type DROP DEFAULT,
+    ALTER COLUMN payload DROP DEFAULT;
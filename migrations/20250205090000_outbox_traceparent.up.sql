@@ -0,0 +1,2 @@
+-- W3C traceparent of the request that enqueued the event, sent along when it is published
+ALTER TABLE outbox ADD COLUMN traceparent TEXT;
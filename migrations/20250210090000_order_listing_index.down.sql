@@ -0,0 +1 @@
+DROP INDEX order_details_username_created_idx;
@@ -0,0 +1,2 @@
+-- pages of a user's orders are read in created_at order, starting after a cursor
+CREATE INDEX order_details_username_created_idx ON order_details (username, created_at, id);
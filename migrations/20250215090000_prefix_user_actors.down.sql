@@ -0,0 +1,3 @@
+UPDATE order_status_history
+SET actor = substr(actor, length('user:') + 1)
+WHERE actor LIKE 'user:%';
@@ -0,0 +1,5 @@
+-- usernames get a prefix so that they can't be mistaken for the other actors
+UPDATE order_status_history
+SET actor = 'user:' || actor
+WHERE actor NOT IN ('stripe-webhook', 'stripe-checkout')
+  AND actor NOT LIKE 'service:%';
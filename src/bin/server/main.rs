@@ -1,64 +1,123 @@
+use bachelorarbeit::config::Config;
+use bachelorarbeit::health::{connect_with_backoff, monitor, Readiness};
+use bachelorarbeit::domain::models::order_totals::PricingPolicy;
+use bachelorarbeit::domain::ports::product_catalog::ProductCatalog;
 use bachelorarbeit::domain::services::order_service::DefaultOrderService;
+use bachelorarbeit::domain::services::outbox_relay::OutboxRelay;
 use bachelorarbeit::domain::services::payment_service::StripeService;
-use bachelorarbeit::inbound::http::authorization::keycloak::fetch_jwk_set;
+use bachelorarbeit::inbound::http::authorization::oidc::TokenVerifier;
 use bachelorarbeit::inbound::http::{HttpServer, HttpServerConfig};
+use bachelorarbeit::metrics::METRICS;
+use bachelorarbeit::telemetry;
 use bachelorarbeit::outbound::postgres::Postgres;
+use bachelorarbeit::outbound::product_service::ProductServiceCatalog;
 use bachelorarbeit::outbound::rabbitmq::RabbitMQ;
 use dotenv::dotenv;
-use jsonwebtoken::{Algorithm, Validation};
 use std::sync::Arc;
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let keycloak_url = std::env::var("KEYCLOAK_URL")
-        .expect("KEYCLOAK_URL not set");
-    let keycloak_issuer = std::env::var("KEYCLOAK_ISSUER")
-        .expect("KEYCLOAK_ISSUER not set");
-    let keys = fetch_jwk_set(&keycloak_url)
-        .await
-        .expect("Failed to fetch JWK Set");
-    let secret_key = std::env::var("STRIPE_SK")
-        .expect("missing stripe secret key");
-    let postgres_url = std::env::var("DATABASE_URL")
-        .expect("missing DATABASE_URL");
-    let domain =  std::env::var("STRIPE_REDIRECT_URL")
-        .expect("missing STRIPE_REDIRECT_URL not set");
-    let rabbit_host = std::env::var("RABBIT_HOST").expect("missing RABBIT_HOST");
-
+    telemetry::init_logging();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    // tokens are refused and readiness fails until Keycloak has been reached
+    let verifier = TokenVerifier::default();
+    let discovery = verifier.clone();
+    let auth = config.auth.clone();
+    tokio::spawn(async move {
+        let (keys, validation) = connect_with_backoff("keycloak", || auth.discover()).await;
+        discovery.set(keys.clone(), validation);
+        keys.run().await;
+    });
 
     let payment_service = Arc::new(
-        StripeService::new(secret_key.clone(),
-                           domain.to_string(),
+        StripeService::new(config.stripe.secret_key.clone(),
+                           config.stripe.redirect_url.clone(),
+                           config.stripe.webhook_secret.clone(),
         )
+            .with_shipping_countries(&config.stripe.shipping_countries)
     );
-    let postgres = Postgres::new(&postgres_url)
-        .await
-        .unwrap();
-    let rabbit_mq = RabbitMQ::new(
-        &rabbit_host,
-        5672,
-        "Checkout_ToBasket",
-        "BasketExchange",
-    )
-        .await;
+
+    let postgres = match Postgres::connect_lazy(&config.postgres) {
+        Ok(postgres) => postgres,
+        Err(e) => {
+            tracing::error!("{e:#}");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = METRICS.register(postgres.pool_metrics()) {
+        tracing::error!("failed to register database pool metrics: {e}");
+    }
+    let database = postgres.clone();
+    tokio::spawn(monitor("postgres", move || {
+        let database = database.clone();
+        async move { database.ping().await }
+    }));
+
+    let rabbit_mq = RabbitMQ::new(config.rabbitmq.clone());
+    let broker = rabbit_mq.clone();
+    tokio::spawn(monitor("rabbitmq", move || {
+        let broker = broker.clone();
+        async move { broker.ensure_connected().await }
+    }));
+    // messages stay in the outbox while the broker is down
+    tokio::spawn(OutboxRelay::new(postgres.clone(), rabbit_mq.clone()).run());
+
+    let readiness = Readiness::default()
+        .critical("postgres", postgres.clone())
+        .critical("keycloak", verifier.clone())
+        .optional("rabbitmq", rabbit_mq);
+
+    match &config.product_service_url {
+        Some(url) => {
+            let catalog = ProductServiceCatalog::new(url);
+            serve(postgres, payment_service, catalog, verifier, readiness, &config.http).await;
+        }
+        None => {
+            let catalog = postgres.clone();
+            serve(postgres, payment_service, catalog, verifier, readiness, &config.http).await;
+        }
+    }
+}
+
+async fn serve(
+    postgres: Postgres,
+    payment_service: Arc<StripeService>,
+    catalog: impl ProductCatalog,
+    verifier: TokenVerifier,
+    readiness: Readiness,
+    config: &HttpServerConfig,
+) {
+    let pricing = match PricingPolicy::standard() {
+        Ok(pricing) => pricing,
+        Err(e) => {
+            tracing::error!("invalid pricing policy: {e}");
+            std::process::exit(1);
+        }
+    };
     let order_service = DefaultOrderService::new(
         postgres,
-        rabbit_mq,
         payment_service.clone(),
+        catalog,
+        pricing,
     );
-    let config = HttpServerConfig { port: "8080" };
-    let mut validator = Validation::new(Algorithm::RS256);
-    validator.set_issuer(&[keycloak_issuer]);
-    validator.set_audience(&["account"]);
 
-    HttpServer::new(
+    if let Err(e) = HttpServer::new(
         order_service,
         payment_service,
-        keys,
-        validator,
-        &config,
+        verifier,
+        readiness,
+        config,
     )
         .await
-        .expect("server crashed");
+    {
+        tracing::error!("{e:#}");
+        std::process::exit(1);
+    }
 }
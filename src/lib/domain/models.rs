@@ -1,3 +1,11 @@
+pub mod caller;
 pub mod order_details;
 pub mod order_item;
-pub mod order;
\ No newline at end of file
+pub mod order;
+pub mod order_event;
+pub mod order_history;
+pub mod order_page;
+pub mod order_totals;
+pub mod outbox;
+pub mod payment_event;
+pub mod product;
\ No newline at end of file
@@ -0,0 +1,50 @@
+use crate::domain::models::order_details::{OrderDetails, UserName};
+use crate::domain::models::order_history::{Actor, StatusChange};
+
+/// On whose behalf the service is called. Users only get to see and change their own
+/// orders, admins and other services all of them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Caller {
+    User { username: UserName, is_admin: bool },
+    /// Another service, authenticated with its own client credentials.
+    Service { client_id: String },
+}
+
+impl Caller {
+    #[must_use]
+    pub const fn user(username: UserName, is_admin: bool) -> Self {
+        Self::User { username, is_admin }
+    }
+
+    #[must_use]
+    pub fn service(client_id: &str) -> Self {
+        Self::Service { client_id: client_id.to_string() }
+    }
+
+    #[must_use]
+    pub fn can_access(&self, details: &OrderDetails) -> bool {
+        match self {
+            Self::User { username, is_admin } => *is_admin || details.username() == username,
+            Self::Service { .. } => true,
+        }
+    }
+
+    /// The history outlives the order, so its owner is whoever created it.
+    #[must_use]
+    pub fn can_access_history(&self, history: &[StatusChange]) -> bool {
+        match self {
+            Self::User { is_admin: true, .. } | Self::Service { .. } => true,
+            Self::User { .. } => history
+                .first()
+                .is_some_and(|creation| creation.actor() == &self.actor()),
+        }
+    }
+
+    #[must_use]
+    pub fn actor(&self) -> Actor {
+        match self {
+            Self::User { username, .. } => Actor::User(username.clone()),
+            Self::Service { client_id } => Actor::Service(client_id.clone()),
+        }
+    }
+}
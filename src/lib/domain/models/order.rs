@@ -1,9 +1,12 @@
 use derive_more::From;
+use rust_decimal::Decimal;
 use getset::Getters;
 use thiserror::Error;
 use uuid::Uuid;
-use crate::domain::models::order_details::{OrderDetails, SessionStatus, UserName};
-use crate::domain::models::order_item::{CreateOrderItemRequest, OrderItem};
+use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+use crate::domain::models::order_history::Actor;
+use crate::domain::models::order_item::{CreateOrderItemRequest, Currency, OrderItem, Price};
+use crate::domain::models::order_totals::OrderTotals;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Getters)]
 #[getset(get = "pub")]
@@ -17,9 +20,33 @@ impl Order {
         if items.is_empty() {
             return Err(CreateOrderError::NoItems);
         }
+        ensure_currency(*order_details.currency(), items.iter().map(OrderItem::price))?;
         
         Ok(Self { details: order_details, items })
     }
+
+    /// Subtotal, tax, shipping and total as fixed at creation time.
+    #[must_use]
+    pub fn totals(&self) -> &OrderTotals {
+        self.details.totals()
+    }
+
+    /// Moves the order to `next`. Returns `Ok(false)` if it already is in `next`.
+    ///
+    /// # Errors
+    /// [`UpdateOrderError::InvalidTransition`] if the current status can't change to `next`.
+    pub fn transition_to(&mut self, next: OrderStatus) -> Result<bool, UpdateOrderError> {
+        let current = *self.details.status();
+        if current == next {
+            return Ok(false);
+        }
+        if !current.can_transition_to(next) {
+            return Err(UpdateOrderError::InvalidTransition { from: current, to: next });
+        }
+
+        self.details.set_status(next);
+        Ok(true)
+    }
 }
 
 
@@ -28,25 +55,35 @@ impl Order {
 pub struct CreateOrderRequest {
     id: Uuid,
     username: UserName, 
+    currency: Currency,
     items: Vec<CreateOrderItemRequest>,
 }
 
 impl CreateOrderRequest {
-    pub fn new(username: UserName, items: Vec<CreateOrderItemRequest>) -> Self {
-        Self {id: Uuid::new_v4(), username, items }
+    #[must_use]
+    pub fn new(username: UserName, currency: Currency, items: Vec<CreateOrderItemRequest>) -> Self {
+        Self {id: Uuid::new_v4(), username, currency, items }
     }
 }
 
+fn ensure_currency<'a>(expected: Currency, mut prices: impl Iterator<Item = &'a Price>) -> Result<(), CreateOrderError> {
+    prices
+        .find(|price| price.currency() != &expected)
+        .map_or(Ok(()), |price| Err(CreateOrderError::CurrencyMismatch { expected, found: *price.currency() }))
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, From, Getters)]
 #[getset(get = "pub")]
 pub struct UpdateOrderStatusRequest {
     id: Uuid,
-    status: Option<SessionStatus>,
+    status: OrderStatus,
+    actor: Actor,
 }
 
 impl UpdateOrderStatusRequest {
-    pub fn new(id: Uuid, status: Option<SessionStatus>) -> Self {
-        Self { id, status }
+    #[must_use]
+    pub const fn new(id: Uuid, status: OrderStatus, actor: Actor) -> Self {
+        Self { id, status, actor }
     }
     
 }
@@ -55,6 +92,10 @@ impl UpdateOrderStatusRequest {
 pub enum FindOrderError {
     #[error("cannot find order with id {id}")]
     IdNotFound { id: uuid::Uuid },
+    #[error("cannot find order for session {session_id}")]
+    SessionNotFound { session_id: SessionId },
+    #[error("order belongs to another user")]
+    Forbidden,
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
@@ -63,6 +104,12 @@ pub enum FindOrderError {
 pub enum CreateOrderError {
     #[error("Order must contain items")]
     NoItems,
+    #[error("Order is in {expected} but contains an item priced in {found}")]
+    CurrencyMismatch { expected: Currency, found: Currency },
+    #[error("Product {item_id} is unknown or no longer available")]
+    UnavailableProduct { item_id: Uuid },
+    #[error("Order total {total} {currency} must be below {}", OrderTotals::UPPER_BOUND)]
+    TotalTooLarge { total: Decimal, currency: Currency },
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
     
@@ -80,6 +127,12 @@ pub enum DeleteOrderError {
 pub enum UpdateOrderError {
     #[error("order does not exist")]
     NotFound,
+    #[error("order cannot move from {from} to {to}")]
+    InvalidTransition { from: OrderStatus, to: OrderStatus },
+    #[error("order was modified concurrently")]
+    Conflict,
+    #[error("order belongs to another user")]
+    Forbidden,
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
@@ -88,27 +141,36 @@ pub enum UpdateOrderError {
 #[cfg(test)]
 mod tests {
     use chrono::Utc;
-    use crate::domain::models::order::{CreateOrderError, Order};
-    use crate::domain::models::order_details::{OrderDetails, SessionId, SessionStatus, UserName};
-    use crate::domain::models::order_item::{OrderItem, Price, ProductName};
+    use rust_decimal::Decimal;
+    use crate::domain::models::order::{CreateOrderError, Order, UpdateOrderError};
+    use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+    use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+    use crate::domain::models::order_totals::OrderTotals;
 
     fn create_order_details() -> OrderDetails {
         let id = uuid::Uuid::new_v4();
         let username = UserName::new("Hannes");
-        let status = Some(SessionStatus::Open);
+        let status = OrderStatus::AwaitingPayment;
         let session_id = SessionId::new("meine session");
         let created_at = Utc::now();
         
-        OrderDetails::new(id, username, status, session_id, created_at)
+        let totals = OrderTotals::new(Decimal::TEN, Decimal::new(160, 2), Decimal::ZERO, Decimal::TEN);
+
+        OrderDetails::new(id, username, status, session_id, Currency::Eur, totals, created_at)
     }
     
     fn create_order_item() -> OrderItem {
+        create_order_item_in(Currency::Eur)
+    }
+
+    fn create_order_item_in(currency: Currency) -> OrderItem {
         let id = uuid::Uuid::new_v4();
         let product_name = ProductName::new("Testprodukt");
         let item_id = uuid::Uuid::new_v4();
-        let price = Price::new(5.0).unwrap();
+        let price = Price::from_minor_units(500, currency).unwrap();
+        let quantity = Quantity::new(2).unwrap();
         
-        OrderItem::new(id, product_name, item_id, price)
+        OrderItem::new(id, product_name, item_id, price, quantity)
     }
     #[test]
     fn order_new() {
@@ -127,4 +189,49 @@ mod tests {
         
         matches!(order_result, Err(CreateOrderError::NoItems));
     }
+
+    #[test]
+    fn order_mixed_currencies() {
+        let details = create_order_details();
+        let items = vec![create_order_item(), create_order_item_in(Currency::Usd)];
+        let order_result = Order::new(details, items);
+
+        assert!(matches!(
+            order_result,
+            Err(CreateOrderError::CurrencyMismatch { expected: Currency::Eur, found: Currency::Usd })
+        ));
+    }
+
+    #[test]
+    fn order_transition_to_paid() {
+        let mut order = Order::new(create_order_details(), vec![create_order_item()]).unwrap();
+
+        let changed = order.transition_to(OrderStatus::Paid).unwrap();
+
+        assert!(changed);
+        assert_eq!(order.details().status(), &OrderStatus::Paid);
+    }
+
+    #[test]
+    fn order_transition_to_same_status() {
+        let mut order = Order::new(create_order_details(), vec![create_order_item()]).unwrap();
+
+        let changed = order.transition_to(OrderStatus::AwaitingPayment).unwrap();
+
+        assert!(!changed);
+    }
+
+    #[test]
+    fn order_invalid_transition() {
+        let mut order = Order::new(create_order_details(), vec![create_order_item()]).unwrap();
+        order.transition_to(OrderStatus::Paid).unwrap();
+
+        let result = order.transition_to(OrderStatus::AwaitingPayment);
+
+        assert!(matches!(
+            result,
+            Err(UpdateOrderError::InvalidTransition { from: OrderStatus::Paid, to: OrderStatus::AwaitingPayment })
+        ));
+        assert_eq!(order.details().status(), &OrderStatus::Paid);
+    }
 }
@@ -1,22 +1,32 @@
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::types::uuid;
+use std::str::FromStr;
 use derive_more::{Display, From};
 use getset::Getters;
 use uuid::Uuid;
+use crate::domain::models::order_item::Currency;
+use crate::domain::models::order_totals::OrderTotals;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Getters)]
 #[getset(get = "pub")]
 pub struct OrderDetails {
     order_id: Uuid,
     username: UserName,
-    status: Option<SessionStatus>,
+    status: OrderStatus,
     session_id: SessionId,
+    currency: Currency,
+    totals: OrderTotals,
     created_at: DateTime<Utc>,
 }
 
 impl OrderDetails {
-    pub fn new(id: Uuid, username: UserName, status: Option<SessionStatus>, session_id: SessionId, created_at: DateTime<Utc>) -> Self {
-        Self { order_id: id, username, status, session_id, created_at}
+    #[must_use]
+    pub const fn new(id: Uuid, username: UserName, status: OrderStatus, session_id: SessionId, currency: Currency, totals: OrderTotals, created_at: DateTime<Utc>) -> Self {
+        Self { order_id: id, username, status, session_id, currency, totals, created_at}
+    }
+
+    pub(crate) const fn set_status(&mut self, status: OrderStatus) {
+        self.status = status;
     }
 }
 
@@ -30,6 +40,7 @@ impl UserName {
     }
 }
 
+/// State of the payment provider's checkout session.
 #[derive(Display, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, From)]
 pub enum SessionStatus {
     Open,
@@ -37,6 +48,67 @@ pub enum SessionStatus {
     Expired,
 }
 
+/// Lifecycle of an order:
+/// `Pending -> AwaitingPayment -> Paid -> Fulfilled`, with `Cancelled` and `Expired`
+/// ending unpaid orders and `Refunded` ending paid ones.
+#[derive(Display, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum OrderStatus {
+    Pending,
+    AwaitingPayment,
+    Paid,
+    Fulfilled,
+    Cancelled,
+    Expired,
+    Refunded,
+}
+
+impl OrderStatus {
+    #[must_use]
+    pub const fn can_transition_to(self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            (Self::Pending, Self::AwaitingPayment | Self::Cancelled | Self::Expired)
+                | (Self::AwaitingPayment, Self::Paid | Self::Cancelled | Self::Expired)
+                | (Self::Paid, Self::Fulfilled | Self::Refunded)
+                | (Self::Fulfilled, Self::Refunded)
+        )
+    }
+
+    #[must_use]
+    pub const fn is_terminal(self) -> bool {
+        matches!(self, Self::Cancelled | Self::Expired | Self::Refunded)
+    }
+}
+
+/// Accepts the displayed name as well as `snake_case`, in any case, e.g. `AwaitingPayment`
+/// or `awaiting_payment`.
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status.replace('_', "").to_ascii_lowercase().as_str() {
+            "pending" => Ok(Self::Pending),
+            "awaitingpayment" => Ok(Self::AwaitingPayment),
+            "paid" => Ok(Self::Paid),
+            "fulfilled" => Ok(Self::Fulfilled),
+            "cancelled" => Ok(Self::Cancelled),
+            "expired" => Ok(Self::Expired),
+            "refunded" => Ok(Self::Refunded),
+            _ => Err(format!("unknown order status {status}")),
+        }
+    }
+}
+
+impl From<SessionStatus> for OrderStatus {
+    fn from(status: SessionStatus) -> Self {
+        match status {
+            SessionStatus::Open => Self::AwaitingPayment,
+            SessionStatus::Complete => Self::Paid,
+            SessionStatus::Expired => Self::Expired,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
 pub struct SessionId(String);
 
@@ -48,7 +120,7 @@ impl SessionId {
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::models::order_details::{SessionId, UserName};
+    use crate::domain::models::order_details::{OrderStatus, SessionId, UserName};
 
     #[test]
     fn new_username_trim() {
@@ -62,4 +134,35 @@ mod tests {
         let session_id = SessionId::new("abc");
         assert_eq!(session_id.to_string(), "abc");
     }
+
+    #[test]
+    fn paid_order_cannot_expire() {
+        assert!(OrderStatus::AwaitingPayment.can_transition_to(OrderStatus::Paid));
+        assert!(!OrderStatus::Paid.can_transition_to(OrderStatus::Expired));
+        assert!(!OrderStatus::Paid.can_transition_to(OrderStatus::AwaitingPayment));
+    }
+
+    #[test]
+    fn terminal_states_are_final() {
+        let all = [
+            OrderStatus::Pending,
+            OrderStatus::AwaitingPayment,
+            OrderStatus::Paid,
+            OrderStatus::Fulfilled,
+            OrderStatus::Cancelled,
+            OrderStatus::Expired,
+            OrderStatus::Refunded,
+        ];
+
+        for from in all.into_iter().filter(|s| s.is_terminal()) {
+            assert!(all.iter().all(|to| !from.can_transition_to(*to)), "{from} should be terminal");
+        }
+    }
+
+    #[test]
+    fn parses_displayed_and_snake_case_status() {
+        assert_eq!("AwaitingPayment".parse::<OrderStatus>(), Ok(OrderStatus::AwaitingPayment));
+        assert_eq!("awaiting_payment".parse::<OrderStatus>(), Ok(OrderStatus::AwaitingPayment));
+        assert!("shipped".parse::<OrderStatus>().is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+use getset::Getters;
+use uuid::Uuid;
+use crate::domain::models::order::Order;
+use crate::domain::models::order_details::OrderStatus;
+
+/// Something that happened to an order that other services are told about. Carries
+/// the order as it was right after the change, or right before it was deleted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct OrderEvent {
+    id: Uuid,
+    kind: OrderEventKind,
+    order: Order,
+    occurred_at: DateTime<Utc>,
+}
+
+impl OrderEvent {
+    #[must_use]
+    pub const fn new(id: Uuid, kind: OrderEventKind, order: Order, occurred_at: DateTime<Utc>) -> Self {
+        Self { id, kind, order, occurred_at }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+pub enum OrderEventKind {
+    #[display("OrderCreated")]
+    Created,
+    #[display("OrderPaid")]
+    Paid,
+    #[display("OrderExpired")]
+    Expired,
+    #[display("OrderCancelled")]
+    Cancelled,
+    #[display("OrderDeleted")]
+    Deleted,
+}
+
+impl OrderEventKind {
+    /// Lower case name without the `Order` prefix, e.g. `paid`.
+    #[must_use]
+    pub const fn short_name(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Paid => "paid",
+            Self::Expired => "expired",
+            Self::Cancelled => "cancelled",
+            Self::Deleted => "deleted",
+        }
+    }
+
+    /// The event for an order that moved to `status`, `None` for the intermediate
+    /// statuses nobody outside is interested in.
+    #[must_use]
+    pub const fn for_status(status: OrderStatus) -> Option<Self> {
+        match status {
+            OrderStatus::Paid => Some(Self::Paid),
+            OrderStatus::Expired => Some(Self::Expired),
+            OrderStatus::Cancelled => Some(Self::Cancelled),
+            OrderStatus::Pending
+            | OrderStatus::AwaitingPayment
+            | OrderStatus::Fulfilled
+            | OrderStatus::Refunded => None,
+        }
+    }
+}
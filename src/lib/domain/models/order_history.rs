@@ -0,0 +1,96 @@
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+use getset::Getters;
+use uuid::Uuid;
+use crate::domain::models::order_details::{OrderStatus, UserName};
+
+const STRIPE_WEBHOOK: &str = "stripe-webhook";
+const STRIPE_CHECKOUT: &str = "stripe-checkout";
+const SERVICE_PREFIX: &str = "service:";
+/// Keeps usernames apart from the other actors, a user may well be called `stripe-webhook`.
+const USER_PREFIX: &str = "user:";
+
+/// Who caused a status change.
+#[derive(Display, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum Actor {
+    #[display("{USER_PREFIX}{_0}")]
+    User(UserName),
+    /// A payment event delivered by Stripe.
+    #[display("{STRIPE_WEBHOOK}")]
+    StripeWebhook,
+    /// The checkout session status, fetched when the customer returns from Stripe.
+    #[display("{STRIPE_CHECKOUT}")]
+    StripeCheckout,
+    /// Another service, identified by its `OAuth2` client id.
+    #[display("{SERVICE_PREFIX}{_0}")]
+    Service(String),
+}
+
+impl FromStr for Actor {
+    type Err = String;
+
+    fn from_str(actor: &str) -> Result<Self, Self::Err> {
+        match actor {
+            STRIPE_WEBHOOK => Ok(Self::StripeWebhook),
+            STRIPE_CHECKOUT => Ok(Self::StripeCheckout),
+            actor => actor
+                .strip_prefix(USER_PREFIX)
+                .map(|username| Self::User(UserName::new(username)))
+                .or_else(|| actor.strip_prefix(SERVICE_PREFIX).map(|client_id| Self::Service(client_id.to_string())))
+                .ok_or_else(|| format!("unknown actor {actor}")),
+        }
+    }
+}
+
+/// One entry of an order's status history. `previous_status` is `None` for the
+/// creation of the order, `new_status` is `None` for its deletion.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct StatusChange {
+    order_id: Uuid,
+    previous_status: Option<OrderStatus>,
+    new_status: Option<OrderStatus>,
+    actor: Actor,
+    changed_at: DateTime<Utc>,
+}
+
+impl StatusChange {
+    #[must_use]
+    pub const fn new(
+        order_id: Uuid,
+        previous_status: Option<OrderStatus>,
+        new_status: Option<OrderStatus>,
+        actor: Actor,
+        changed_at: DateTime<Utc>,
+    ) -> Self {
+        Self { order_id, previous_status, new_status, actor, changed_at }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::models::order_details::UserName;
+    use crate::domain::models::order_history::Actor;
+
+    #[test]
+    fn actor_round_trips_through_its_display() {
+        let actors = [
+            Actor::User(UserName::new("Hannes")),
+            Actor::User(UserName::new("stripe-webhook")),
+            Actor::User(UserName::new("service:basket")),
+            Actor::StripeWebhook,
+            Actor::StripeCheckout,
+            Actor::Service("basket".to_string()),
+        ];
+
+        for actor in actors {
+            assert_eq!(actor.to_string().parse::<Actor>().unwrap(), actor);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_actor() {
+        assert!("Hannes".parse::<Actor>().is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use derive_more::{AsRef, Display, From};
+use std::str::FromStr;
+use derive_more::{Display, From};
 use getset::Getters;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::prelude::ToPrimitive;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -12,11 +13,19 @@ pub struct OrderItem {
     product_name: ProductName,
     item_id: uuid::Uuid,
     price: Price,
+    quantity: Quantity,
 }
 
 impl OrderItem {
-    pub fn new(id: Uuid, product_name: ProductName, item_id: uuid::Uuid, price: Price) -> Self {
-        Self {id, product_name, item_id, price,}
+    #[must_use]
+    pub const fn new(id: Uuid, product_name: ProductName, item_id: uuid::Uuid, price: Price, quantity: Quantity) -> Self {
+        Self {id, product_name, item_id, price, quantity}
+    }
+
+    /// Unit price times quantity.
+    #[must_use]
+    pub fn total(&self) -> Price {
+        self.price.times(self.quantity)
     }
 }
 
@@ -29,30 +38,142 @@ impl ProductName {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, AsRef)]
-pub struct Price(Decimal);
+/// ISO 4217 currencies we accept payments in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+pub enum Currency {
+    #[display("EUR")]
+    Eur,
+    #[display("USD")]
+    Usd,
+    #[display("GBP")]
+    Gbp,
+    #[display("CHF")]
+    Chf,
+    #[display("JPY")]
+    Jpy,
+}
+
+#[derive(Clone, Debug, Error)]
+#[error("unsupported currency {0}")]
+pub struct CurrencyError(String);
+
+impl Currency {
+    /// Number of decimal places of the currency's minor unit, 0 for zero-decimal
+    /// currencies like JPY.
+    #[must_use]
+    pub const fn minor_unit_exponent(self) -> u32 {
+        match self {
+            Self::Jpy => 0,
+            Self::Eur | Self::Usd | Self::Gbp | Self::Chf => 2,
+        }
+    }
+
+    /// `amount` in the currency's smallest unit, e.g. cents for EUR and yen for JPY.
+    #[must_use]
+    pub fn to_minor_units(self, amount: Decimal) -> Option<i64> {
+        (amount * Decimal::from(10_i64.pow(self.minor_unit_exponent()))).to_i64()
+    }
+}
+
+impl FromStr for Currency {
+    type Err = CurrencyError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_ascii_uppercase().as_str() {
+            "EUR" => Ok(Self::Eur),
+            "USD" => Ok(Self::Usd),
+            "GBP" => Ok(Self::Gbp),
+            "CHF" => Ok(Self::Chf),
+            "JPY" => Ok(Self::Jpy),
+            _ => Err(CurrencyError(raw.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, Getters)]
+#[display("{amount} {currency}")]
+#[getset(get = "pub")]
+pub struct Price {
+    amount: Decimal,
+    currency: Currency,
+}
 
 #[derive(Clone, Debug, Error)]
 pub enum PriceError {
     #[error("price has to be positive")]
     Negative,
-    #[error("cannot be represented with this input")]
-    Unrepresentable,
+    #[error("price must be below {}", Price::UPPER_BOUND)]
+    TooLarge,
+    #[error("{currency} has no more than {} decimal places", currency.minor_unit_exponent())]
+    TooPrecise { currency: Currency },
 }
 
 impl Price {
-    pub fn new(raw: f64) -> Result<Self, PriceError> {
-        let value = Decimal::from_f64(raw).ok_or(PriceError::Unrepresentable)?;
-        if value <= Decimal::ZERO {
+    /// Exclusive, the `order_item.price` column is a `NUMERIC(10,2)`.
+    const UPPER_BOUND: Decimal = Decimal::from_parts(100_000_000, 0, 0, false, 0);
+
+    /// # Errors
+    /// [`PriceError`] if `amount` isn't positive, too large for the database or has more
+    /// decimal places than `currency`.
+    pub fn from_decimal(amount: Decimal, currency: Currency) -> Result<Self, PriceError> {
+        if amount <= Decimal::ZERO {
             Err(PriceError::Negative)
+        } else if amount >= Self::UPPER_BOUND {
+            Err(PriceError::TooLarge)
+        } else if amount.normalize().scale() > currency.minor_unit_exponent() {
+            Err(PriceError::TooPrecise { currency })
         } else {
-            Ok(Self(value))
+            Ok(Self { amount, currency })
         }
 
     }
 
-    pub fn as_cents(&self) -> Option<i64> {
-        (self.0 * Decimal::new(100, 0)).to_i64()
+    /// Takes the amount in the currency's smallest unit, e.g. 1999 for 19.99 EUR.
+    ///
+    /// # Errors
+    /// See [`from_decimal`](Self::from_decimal).
+    pub fn from_minor_units(units: i64, currency: Currency) -> Result<Self, PriceError> {
+        Self::from_decimal(Decimal::new(units, currency.minor_unit_exponent()), currency)
+    }
+
+    /// The amount in the currency's smallest unit, e.g. cents for EUR and yen for JPY.
+    #[must_use]
+    pub fn as_minor_units(&self) -> Option<i64> {
+        self.currency.to_minor_units(self.amount)
+    }
+
+    fn times(&self, quantity: Quantity) -> Self {
+        Self { amount: self.amount * Decimal::from(quantity.0), currency: self.currency }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+pub struct Quantity(u32);
+
+#[derive(Clone, Debug, Error)]
+pub enum QuantityError {
+    #[error("quantity has to be at least 1")]
+    Zero,
+    #[error("quantity cannot exceed {max}")]
+    TooLarge { max: u32 },
+}
+
+impl Quantity {
+    pub const MAX: u32 = 999;
+
+    /// # Errors
+    /// [`QuantityError`] if `raw` is 0 or above [`MAX`](Self::MAX).
+    pub const fn new(raw: u32) -> Result<Self, QuantityError> {
+        match raw {
+            0 => Err(QuantityError::Zero),
+            raw if raw > Self::MAX => Err(QuantityError::TooLarge { max: Self::MAX }),
+            raw => Ok(Self(raw)),
+        }
+    }
+
+    #[must_use]
+    pub const fn value(self) -> u32 {
+        self.0
     }
 }
 
@@ -60,31 +181,96 @@ impl Price {
 #[getset(get = "pub")]
 pub struct CreateOrderItemRequest {
     id: Uuid,
-    product_name: ProductName,
     item_id: Uuid,
-    price: Price,
+    quantity: Quantity,
 }
 
 impl CreateOrderItemRequest {
-    pub fn new(product_name: ProductName, item_id: uuid::Uuid, price: Price) -> Self {
-        Self {id: Uuid::new_v4(), product_name, item_id, price}
+    /// Name and price are resolved from the [`ProductCatalog`](crate::domain::ports::product_catalog::ProductCatalog).
+    #[must_use]
+    pub fn new(item_id: uuid::Uuid, quantity: Quantity) -> Self {
+        Self {id: Uuid::new_v4(), item_id, quantity}
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::models::order_item::{Price, PriceError};
+    use std::str::FromStr;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+    use crate::domain::models::order_item::{Currency, OrderItem, Price, PriceError, ProductName, Quantity, QuantityError};
 
     #[test]
     fn price_negative_error() {
-        let price_result = Price::new(-12.0);
+        let price_result = Price::from_decimal(Decimal::new(-1200, 2), Currency::Eur);
         assert!(matches!(price_result, Err(PriceError::Negative)));
     }
 
     #[test]
     fn price_zero_error() {
-        let price_result = Price::new(0.0);
+        let price_result = Price::from_minor_units(0, Currency::Eur);
         assert!(matches!(price_result, Err(PriceError::Negative)));
     }
 
+    #[test]
+    fn price_too_large_error() {
+        let price_result = Price::from_decimal(Decimal::from(100_000_000), Currency::Eur);
+        assert!(matches!(price_result, Err(PriceError::TooLarge)));
+    }
+
+    #[test]
+    fn price_is_exact() {
+        let price = Price::from_decimal(Decimal::from_str("19.99").unwrap(), Currency::Eur).unwrap();
+
+        assert_eq!(price.amount().to_string(), "19.99");
+        assert_eq!(price, Price::from_minor_units(1999, Currency::Eur).unwrap());
+    }
+
+    #[test]
+    fn price_minor_units() {
+        assert_eq!(Price::from_minor_units(1999, Currency::Eur).unwrap().as_minor_units(), Some(1999));
+        assert_eq!(Price::from_minor_units(1500, Currency::Jpy).unwrap().as_minor_units(), Some(1500));
+        assert_eq!(Price::from_minor_units(1500, Currency::Jpy).unwrap().amount(), &Decimal::from(1500));
+    }
+
+    #[test]
+    fn price_too_precise_error() {
+        let eur = Price::from_decimal(Decimal::from_str("1.999").unwrap(), Currency::Eur);
+        let jpy = Price::from_decimal(Decimal::new(125, 1), Currency::Jpy);
+
+        assert!(matches!(eur, Err(PriceError::TooPrecise { currency: Currency::Eur })));
+        assert!(matches!(jpy, Err(PriceError::TooPrecise { currency: Currency::Jpy })));
+    }
+
+    #[test]
+    fn currency_from_str() {
+        assert_eq!("usd".parse::<Currency>().unwrap(), Currency::Usd);
+        assert!("XYZ".parse::<Currency>().is_err());
+    }
+
+    #[test]
+    fn quantity_zero_error() {
+        let quantity_result = Quantity::new(0);
+        assert!(matches!(quantity_result, Err(QuantityError::Zero)));
+    }
+
+    #[test]
+    fn quantity_too_large_error() {
+        let quantity_result = Quantity::new(Quantity::MAX + 1);
+        assert!(matches!(quantity_result, Err(QuantityError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn item_total_multiplies_price() {
+        let item = OrderItem::new(
+            Uuid::new_v4(),
+            ProductName::new("Monstera"),
+            Uuid::new_v4(),
+            Price::from_minor_units(1250, Currency::Usd).unwrap(),
+            Quantity::new(3).unwrap(),
+        );
+
+        assert_eq!(item.total().as_minor_units(), Some(3750));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use getset::Getters;
+use thiserror::Error;
+use uuid::Uuid;
+use crate::domain::models::order::Order;
+use crate::domain::models::order_details::{OrderStatus, UserName};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ListOrdersError {
+    #[error("page size must be between 1 and {MAX_PAGE_SIZE}, got {0}")]
+    InvalidPageSize(u32),
+    #[error("created_from {from} is not before created_before {before}")]
+    InvalidDateRange { from: DateTime<Utc>, before: DateTime<Utc> },
+    #[error("invalid cursor {0}")]
+    InvalidCursor(String),
+}
+
+/// Orders are sorted by `created_at`, ties are broken by id.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Which of a user's orders are listed. Matches every order by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct OrderFilter {
+    /// Any of these, all statuses if empty.
+    statuses: Vec<OrderStatus>,
+    /// Inclusive.
+    created_from: Option<DateTime<Utc>>,
+    /// Exclusive.
+    created_before: Option<DateTime<Utc>>,
+}
+
+impl OrderFilter {
+    /// # Errors
+    /// [`ListOrdersError::InvalidDateRange`] if `created_from` isn't before `created_before`.
+    pub fn new(
+        statuses: Vec<OrderStatus>,
+        created_from: Option<DateTime<Utc>>,
+        created_before: Option<DateTime<Utc>>,
+    ) -> Result<Self, ListOrdersError> {
+        if let (Some(from), Some(before)) = (created_from, created_before) {
+            if from >= before {
+                return Err(ListOrdersError::InvalidDateRange { from, before });
+            }
+        }
+
+        Ok(Self { statuses, created_from, created_before })
+    }
+}
+
+/// Position of the last order of a page, the next page starts right after it.
+/// Clients get it as an opaque string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct OrderCursor {
+    created_at: DateTime<Utc>,
+    order_id: Uuid,
+}
+
+impl OrderCursor {
+    #[must_use]
+    pub const fn new(created_at: DateTime<Utc>, order_id: Uuid) -> Self {
+        Self { created_at, order_id }
+    }
+
+    #[must_use]
+    pub fn after(order: &Order) -> Self {
+        Self::new(*order.details().created_at(), *order.details().order_id())
+    }
+}
+
+impl Display for OrderCursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.created_at.timestamp_micros(), self.order_id.simple())
+    }
+}
+
+impl FromStr for OrderCursor {
+    type Err = ListOrdersError;
+
+    fn from_str(cursor: &str) -> Result<Self, Self::Err> {
+        let invalid = || ListOrdersError::InvalidCursor(cursor.to_string());
+        let (micros, order_id) = cursor.split_once('_').ok_or_else(invalid)?;
+        let created_at = micros
+            .parse()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?;
+        let order_id = Uuid::try_parse(order_id).map_err(|_| invalid())?;
+
+        Ok(Self { created_at, order_id })
+    }
+}
+
+/// One page of a user's orders.
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct ListOrdersRequest {
+    username: UserName,
+    filter: OrderFilter,
+    sort: SortOrder,
+    limit: u32,
+    /// Start after this order, from the first one if `None`.
+    after: Option<OrderCursor>,
+}
+
+impl ListOrdersRequest {
+    /// # Errors
+    /// [`ListOrdersError::InvalidPageSize`] if `limit` is 0 or above the maximum page size.
+    pub fn new(
+        username: UserName,
+        filter: OrderFilter,
+        sort: SortOrder,
+        limit: Option<u32>,
+        after: Option<OrderCursor>,
+    ) -> Result<Self, ListOrdersError> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(ListOrdersError::InvalidPageSize(limit));
+        }
+
+        Ok(Self { username, filter, sort, limit, after })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct OrderPage {
+    orders: Vec<Order>,
+    /// Orders matching the filter on all pages.
+    total: u64,
+    /// `None` on the last page.
+    next_cursor: Option<OrderCursor>,
+}
+
+impl OrderPage {
+    #[must_use]
+    pub const fn new(orders: Vec<Order>, total: u64, next_cursor: Option<OrderCursor>) -> Self {
+        Self { orders, total, next_cursor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+    use crate::domain::models::order_details::UserName;
+    use crate::domain::models::order_page::{ListOrdersError, ListOrdersRequest, OrderCursor, OrderFilter, SortOrder};
+
+    #[test]
+    fn cursor_round_trips_through_its_display() {
+        let cursor = OrderCursor::new(
+            Utc.with_ymd_and_hms(2025, 2, 1, 10, 0, 0).unwrap() + chrono::Duration::microseconds(42),
+            Uuid::from_u128(7),
+        );
+
+        assert_eq!(cursor.to_string().parse::<OrderCursor>().unwrap(), cursor);
+        assert!("yesterday".parse::<OrderCursor>().is_err());
+        assert!("1738404000000042_not-a-uuid".parse::<OrderCursor>().is_err());
+    }
+
+    #[test]
+    fn rejects_empty_date_range_and_page_size_out_of_bounds() {
+        let day = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+
+        assert!(matches!(
+            OrderFilter::new(vec![], Some(day), Some(day)),
+            Err(ListOrdersError::InvalidDateRange { .. })
+        ));
+        for limit in [0, 101] {
+            assert_eq!(
+                ListOrdersRequest::new(UserName::new("Hannes"), OrderFilter::default(), SortOrder::default(), Some(limit), None),
+                Err(ListOrdersError::InvalidPageSize(limit)),
+            );
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use getset::Getters;
+use rust_decimal::{Decimal, RoundingStrategy};
+use crate::domain::models::order::CreateOrderError;
+use crate::domain::models::order_item::{Currency, OrderItem, Price, PriceError};
+
+/// Amounts of an order in its currency, fixed when the order is created.
+/// Prices include VAT, so `tax` is the share of `total` that is VAT and
+/// `total = subtotal + shipping`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct OrderTotals {
+    subtotal: Decimal,
+    tax: Decimal,
+    shipping: Decimal,
+    total: Decimal,
+}
+
+impl OrderTotals {
+    /// Exclusive, the amount columns of `order_details` are `NUMERIC(12,2)`.
+    pub const UPPER_BOUND: Decimal = Decimal::from_parts(1_410_065_408, 2, 0, false, 0);
+
+    #[must_use]
+    pub const fn new(subtotal: Decimal, tax: Decimal, shipping: Decimal, total: Decimal) -> Self {
+        Self { subtotal, tax, shipping, total }
+    }
+}
+
+/// How orders are priced on top of their items: a VAT rate that is already
+/// included in the item prices and a flat shipping fee per currency.
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct PricingPolicy {
+    vat_rate: Decimal,
+    shipping_fees: HashMap<Currency, Price>,
+}
+
+impl PricingPolicy {
+    #[must_use]
+    pub const fn new(vat_rate: Decimal, shipping_fees: HashMap<Currency, Price>) -> Self {
+        Self { vat_rate, shipping_fees }
+    }
+
+    /// `None` if orders in `currency` ship for free.
+    #[must_use]
+    pub fn shipping_fee(&self, currency: Currency) -> Option<&Price> {
+        self.shipping_fees.get(&currency)
+    }
+
+    /// # Errors
+    /// [`CreateOrderError::TotalTooLarge`] if the total doesn't stay below
+    /// [`OrderTotals::UPPER_BOUND`].
+    pub fn totals(&self, items: &[OrderItem], currency: Currency) -> Result<OrderTotals, CreateOrderError> {
+        let subtotal: Decimal = items.iter().map(|item| *item.total().amount()).sum();
+        let shipping = self.shipping_fee(currency).map_or(Decimal::ZERO, |fee| *fee.amount());
+        let total = subtotal + shipping;
+        if total >= OrderTotals::UPPER_BOUND {
+            return Err(CreateOrderError::TotalTooLarge { total, currency });
+        }
+        let tax = (total * self.vat_rate / (Decimal::ONE + self.vat_rate))
+            .round_dp_with_strategy(currency.minor_unit_exponent(), RoundingStrategy::MidpointAwayFromZero);
+
+        Ok(OrderTotals::new(subtotal, tax, shipping, total))
+    }
+}
+
+impl PricingPolicy {
+    /// German standard VAT and the shipping fees of the shop.
+    ///
+    /// # Errors
+    /// [`PriceError`] if one of the shipping fees isn't a valid price.
+    pub fn standard() -> Result<Self, PriceError> {
+        let shipping_fees = [
+            (Currency::Eur, 499),
+            (Currency::Usd, 599),
+            (Currency::Gbp, 449),
+            (Currency::Chf, 590),
+            (Currency::Jpy, 800),
+        ]
+            .into_iter()
+            .map(|(currency, minor_units)| Ok((currency, Price::from_minor_units(minor_units, currency)?)))
+            .collect::<Result<_, PriceError>>()?;
+
+        Ok(Self::new(Decimal::new(19, 2), shipping_fees))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+    use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+    use crate::domain::models::order::CreateOrderError;
+    use crate::domain::models::order_totals::{OrderTotals, PricingPolicy};
+
+    fn create_item(minor_units: i64, quantity: u32, currency: Currency) -> OrderItem {
+        OrderItem::new(
+            Uuid::new_v4(),
+            ProductName::new("Monstera"),
+            Uuid::new_v4(),
+            Price::from_minor_units(minor_units, currency).unwrap(),
+            Quantity::new(quantity).unwrap(),
+        )
+    }
+
+    #[test]
+    fn totals_include_shipping_and_vat() {
+        let items = vec![create_item(1999, 2, Currency::Eur), create_item(1000, 1, Currency::Eur)];
+
+        let totals = PricingPolicy::standard().unwrap().totals(&items, Currency::Eur).unwrap();
+
+        assert_eq!(*totals.subtotal(), Decimal::new(4998, 2));
+        assert_eq!(*totals.shipping(), Decimal::new(499, 2));
+        assert_eq!(*totals.total(), Decimal::new(5497, 2));
+        // 54.97 * 0.19 / 1.19 = 8.7767...
+        assert_eq!(*totals.tax(), Decimal::new(878, 2));
+    }
+
+    #[test]
+    fn tax_is_rounded_to_whole_yen() {
+        let items = vec![create_item(1200, 1, Currency::Jpy)];
+
+        let totals = PricingPolicy::standard().unwrap().totals(&items, Currency::Jpy).unwrap();
+
+        assert_eq!(*totals.total(), Decimal::from(2000));
+        assert_eq!(*totals.tax(), Decimal::from(319));
+    }
+
+    #[test]
+    fn free_shipping_without_fee() {
+        let items = vec![create_item(1190, 1, Currency::Eur)];
+        let policy = PricingPolicy::new(Decimal::new(19, 2), HashMap::new());
+
+        let totals = policy.totals(&items, Currency::Eur).unwrap();
+
+        assert_eq!(*totals.shipping(), Decimal::ZERO);
+        assert_eq!(*totals.total(), Decimal::new(1190, 2));
+        assert_eq!(*totals.tax(), Decimal::new(190, 2));
+    }
+
+    #[test]
+    fn total_has_to_fit_the_database() {
+        let policy = PricingPolicy::new(Decimal::new(19, 2), HashMap::new());
+        let mut items = vec![create_item(9_999_999_999, 100, Currency::Eur)];
+
+        let totals = policy.totals(&items, Currency::Eur).unwrap();
+
+        assert_eq!(*totals.total(), OrderTotals::UPPER_BOUND - Decimal::new(100, 2));
+
+        items.push(create_item(100, 1, Currency::Eur));
+
+        assert!(matches!(
+            policy.totals(&items, Currency::Eur),
+            Err(CreateOrderError::TotalTooLarge { total, .. }) if total == OrderTotals::UPPER_BOUND
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+use getset::Getters;
+use uuid::Uuid;
+use crate::domain::models::order_event::OrderEvent;
+
+/// An order event that was committed together with the change it describes and
+/// still has to be handed to the [`CheckoutProducer`](crate::domain::ports::checkout_producer::CheckoutProducer).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct OutboxMessage {
+    event: OrderEvent,
+    attempts: u32,
+    /// W3C `traceparent` of the operation that caused the event, if it was traced.
+    traceparent: Option<String>,
+}
+
+impl OutboxMessage {
+    #[must_use]
+    pub const fn new(event: OrderEvent, attempts: u32, traceparent: Option<String>) -> Self {
+        Self { event, attempts, traceparent }
+    }
+
+    /// Same as the id of the event.
+    #[must_use]
+    pub fn id(&self) -> &Uuid {
+        self.event.id()
+    }
+}
@@ -0,0 +1,53 @@
+use derive_more::Display;
+use getset::Getters;
+use crate::domain::models::order_details::{OrderStatus, SessionId};
+
+/// A verified notification from the payment provider about a checkout session.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct PaymentEvent {
+    id: PaymentEventId,
+    session_id: SessionId,
+    kind: PaymentEventKind,
+}
+
+impl PaymentEvent {
+    #[must_use]
+    pub const fn new(id: PaymentEventId, session_id: SessionId, kind: PaymentEventKind) -> Self {
+        Self { id, session_id, kind }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+pub struct PaymentEventId(String);
+
+impl PaymentEventId {
+    #[must_use]
+    pub fn new(raw: &str) -> Self {
+        Self(raw.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum PaymentEventKind {
+    /// The customer finished the checkout. Delayed payment methods complete
+    /// unpaid and settle with one of the async payment events later.
+    #[display("CheckoutCompleted")]
+    CheckoutCompleted { paid: bool },
+    CheckoutExpired,
+    AsyncPaymentSucceeded,
+    AsyncPaymentFailed,
+}
+
+impl PaymentEventKind {
+    /// The status an order ends up in once this event has been applied.
+    #[must_use]
+    pub const fn order_status(self) -> OrderStatus {
+        match self {
+            Self::CheckoutCompleted { paid: true } | Self::AsyncPaymentSucceeded => OrderStatus::Paid,
+            Self::CheckoutCompleted { paid: false } => OrderStatus::AwaitingPayment,
+            Self::CheckoutExpired => OrderStatus::Expired,
+            Self::AsyncPaymentFailed => OrderStatus::Cancelled,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use getset::Getters;
+use uuid::Uuid;
+use crate::domain::models::order_item::{Price, ProductName};
+
+/// A product as the catalog knows it, priced in the currency it was looked up in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct Product {
+    id: Uuid,
+    name: ProductName,
+    price: Price,
+    active: bool,
+}
+
+impl Product {
+    #[must_use]
+    pub const fn new(id: Uuid, name: ProductName, price: Price, active: bool) -> Self {
+        Self { id, name, price, active }
+    }
+}
@@ -1,4 +1,7 @@
 pub mod order_service;
 pub mod order_repository;
 pub mod checkout_producer;
-pub mod payment_service;
\ No newline at end of file
+pub mod payment_service;
+pub mod outbox_repository;
+pub mod product_catalog;
+pub mod health_check;
\ No newline at end of file
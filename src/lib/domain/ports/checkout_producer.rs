@@ -1,16 +1,24 @@
 use std::future::Future;
 use thiserror::Error;
-use crate::domain::models::order_details::{SessionStatus, UserName};
+use crate::domain::models::order_event::OrderEvent;
 
 pub trait CheckoutProducer: Clone + Send + Sync + 'static {
-    fn notify_order_result(&self,
-                           username: &UserName,
-                           status: &SessionStatus,
+    /// Publishes an event about an order. `event` may be delivered more than once,
+    /// its id stays the same.
+    fn publish_order_event(&self,
+                           event: &OrderEvent,
     ) -> impl Future<Output=Result<(), NotifyError>> + Send;
 }
 
 #[derive(Error, Debug)]
 pub enum NotifyError {
+    #[error("not connected to the message broker")]
+    Unavailable,
+    #[error("the message broker rejected the message")]
+    Rejected,
+    /// Published as mandatory but no queue is bound to its routing key.
+    #[error("the message could not be routed to a queue: {0}")]
+    Unroutable(String),
     #[error(transparent)]
     UnknownError(#[from] anyhow::Error),
 }
\ No newline at end of file
@@ -0,0 +1,17 @@
+use std::future::Future;
+use thiserror::Error;
+
+/// A dependency whose availability decides whether the service is ready for traffic.
+pub trait HealthCheck: Clone + Send + Sync + 'static {
+    /// Reports the current state of the dependency. Should answer quickly and not
+    /// wait for the dependency to come back.
+    fn check(&self) -> impl Future<Output=Result<(), HealthCheckError>> + Send;
+}
+
+#[derive(Error, Debug)]
+pub enum HealthCheckError {
+    #[error("{0}")]
+    Unavailable(String),
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
@@ -1,7 +1,10 @@
 use std::future::Future;
 use uuid::Uuid;
-use crate::domain::models::order_details::{SessionId, SessionStatus, UserName};
+use crate::domain::models::order_details::{OrderStatus, SessionId};
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderPage};
 use crate::domain::models::order::{CreateOrderError, DeleteOrderError, FindOrderError, Order, UpdateOrderError};
+use crate::domain::models::payment_event::PaymentEvent;
 
 pub trait OrderRepository: Clone + Send + Sync + 'static {
     fn find_order_by_session_id(
@@ -9,19 +12,23 @@ pub trait OrderRepository: Clone + Send + Sync + 'static {
          req: &SessionId,
      ) -> impl Future<Output = Result<Order, FindOrderError>> + Send;
     
-    fn find_orders_by_username(
+    /// Orders of the user matching the filter, sorted by `created_at` and id, starting
+    /// after the request's cursor. The total counts the matches on all pages.
+    fn list_orders(
          &self,
-         req: &UserName,
-    ) -> impl Future<Output = Result<Vec<Order>, FindOrderError>> + Send;
+         req: &ListOrdersRequest,
+    ) -> impl Future<Output = Result<OrderPage, FindOrderError>> + Send;
     
     fn create_order(
          &self,
          req: &Order,
      ) -> impl Future<Output = Result<Uuid, CreateOrderError>> + Send;
     
+    /// Deletes the order and records the deletion in its status history.
     fn delete_order(
          &self,
          req: uuid::Uuid,
+         actor: &Actor,
     ) -> impl Future<Output = Result<uuid::Uuid, DeleteOrderError>> + Send;
     
     fn delete_all_orders(
@@ -33,9 +40,31 @@ pub trait OrderRepository: Clone + Send + Sync + 'static {
         req: Uuid,
     ) -> impl Future<Output = Result<Order, FindOrderError>> + Send;
 
+    /// Moves the order from `from` to `to`, appends the change to the status history and
+    /// enqueues a checkout notification in the outbox within the same transaction. Fails with [`UpdateOrderError::Conflict`] if
+    /// the order is no longer in `from`.
     fn update_order_status(
         &self,
         id: &Uuid,
-        status: Option<&SessionStatus>,
+        from: &OrderStatus,
+        to: &OrderStatus,
+        actor: &Actor,
     ) -> impl Future<Output=Result<Order, UpdateOrderError>> + Send;
+
+    /// Records `event` as processed, moves the order from `from` to `to`, appends the
+    /// change to the status history and enqueues a checkout notification in one transaction. Returns `None` without touching the order
+    /// if the event was already processed.
+    fn apply_payment_event(
+        &self,
+        event: &PaymentEvent,
+        id: &Uuid,
+        from: &OrderStatus,
+        to: &OrderStatus,
+    ) -> impl Future<Output=Result<Option<Order>, UpdateOrderError>> + Send;
+
+    /// Status changes of the order, oldest first. Also available for deleted orders.
+    fn find_status_history(
+        &self,
+        id: &Uuid,
+    ) -> impl Future<Output=Result<Vec<StatusChange>, FindOrderError>> + Send;
 }
\ No newline at end of file
@@ -1,7 +1,11 @@
 use std::future::Future;
 use uuid::Uuid;
-use crate::domain::models::order_details::{SessionId, UserName};
+use crate::domain::models::caller::Caller;
+use crate::domain::models::order_details::SessionId;
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderPage};
 use crate::domain::models::order::{CreateOrderError, CreateOrderRequest, DeleteOrderError, FindOrderError, Order, UpdateOrderError, UpdateOrderStatusRequest};
+use crate::domain::models::payment_event::PaymentEvent;
 
 pub trait OrderService: Clone + Send + Sync + 'static {
 
@@ -15,14 +19,17 @@ pub trait OrderService: Clone + Send + Sync + 'static {
         req: &SessionId,
     ) -> impl Future<Output = Result<Order, FindOrderError>> + Send;
 
-    fn find_orders_by_username(
+    /// One page of the user's orders matching the request's filter.
+    fn list_orders(
         &self,
-        req: &UserName,
-    ) -> impl Future<Output = Result<Vec<Order>, FindOrderError>> + Send;
+        req: &ListOrdersRequest,
+    ) -> impl Future<Output = Result<OrderPage, FindOrderError>> + Send;
     
+    /// Fails with [`FindOrderError::Forbidden`] unless the order belongs to `caller`.
     fn find_order_by_id(
         &self,
         req: Uuid,
+        caller: &Caller,
     ) -> impl Future<Output = Result<Order, FindOrderError>> + Send;
     
     
@@ -34,6 +41,7 @@ pub trait OrderService: Clone + Send + Sync + 'static {
     fn delete_order(
         &self,
         req: Uuid,
+        actor: &Actor,
     ) -> impl Future<Output = Result<Uuid, DeleteOrderError>> + Send;
     
     
@@ -45,5 +53,23 @@ pub trait OrderService: Clone + Send + Sync + 'static {
         &self,
         req: UpdateOrderStatusRequest,
     ) -> impl Future<Output = Result<Order, UpdateOrderError>> + Send;
+
+    /// Expires the checkout session of the caller's order and cancels the order.
+    fn cancel_order(
+        &self,
+        req: &SessionId,
+        caller: &Caller,
+    ) -> impl Future<Output = Result<Order, UpdateOrderError>> + Send;
+
+    fn handle_payment_event(
+        &self,
+        event: &PaymentEvent,
+    ) -> impl Future<Output = Result<Order, UpdateOrderError>> + Send;
+
+    fn find_status_history(
+        &self,
+        req: Uuid,
+        caller: &Caller,
+    ) -> impl Future<Output = Result<Vec<StatusChange>, FindOrderError>> + Send;
 }
 
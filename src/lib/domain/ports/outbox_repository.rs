@@ -0,0 +1,33 @@
+use std::future::Future;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+use crate::domain::models::outbox::OutboxMessage;
+
+pub trait OutboxRepository: Clone + Send + Sync + 'static {
+    /// Returns up to `limit` undelivered messages that are due and hides them from
+    /// other relays for `lease`, so a crashed relay's messages are picked up again.
+    fn claim_pending(
+        &self,
+        limit: u32,
+        lease: Duration,
+    ) -> impl Future<Output = Result<Vec<OutboxMessage>, OutboxError>> + Send;
+
+    fn mark_delivered(
+        &self,
+        id: &Uuid,
+    ) -> impl Future<Output = Result<(), OutboxError>> + Send;
+
+    fn record_failure(
+        &self,
+        id: &Uuid,
+        error: &str,
+        retry_in: Duration,
+    ) -> impl Future<Output = Result<(), OutboxError>> + Send;
+}
+
+#[derive(Debug, Error)]
+pub enum OutboxError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
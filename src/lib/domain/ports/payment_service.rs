@@ -2,12 +2,15 @@ use std::future::Future;
 use stripe::CheckoutSession;
 use thiserror::Error;
 use crate::domain::models::order_details::{SessionId, SessionStatus};
-use crate::domain::models::order_item::OrderItem;
+use crate::domain::models::order_item::{OrderItem, Price};
+use crate::domain::models::payment_event::PaymentEvent;
 
 pub trait PaymentService: Clone + Send + Sync + 'static {
+    /// Charges `shipping` on top of the items, if there is a fee.
     fn create_checkout_session(
         &self,
         order_items: &Vec<OrderItem>,
+        shipping: Option<&Price>,
     ) -> impl Future<Output=Result<CheckoutSession, PaymentServiceError>> + Send;
 
     fn retrieve_checkout_status(
@@ -19,6 +22,18 @@ pub trait PaymentService: Clone + Send + Sync + 'static {
         &self,
         id: &SessionId,
     ) -> impl Future<Output=Result<(), PaymentServiceError>> + Send;
+
+    /// Verifies a webhook delivery against its signature header and parses it.
+    /// Events that don't concern checkout sessions yield `Ok(None)`.
+    ///
+    /// # Errors
+    /// [`PaymentServiceError::InvalidWebhook`] if the signature doesn't match or the event
+    /// can't be parsed.
+    fn parse_webhook_event(
+        &self,
+        payload: &str,
+        signature: &str,
+    ) -> Result<Option<PaymentEvent>, PaymentServiceError>;
 }
 
 #[derive(Debug, Error)]
@@ -27,5 +42,7 @@ pub enum PaymentServiceError {
     Unknown(#[from] anyhow::Error),
     #[error("invalid session id {0}")]
     InvalidSessionId(SessionId),
+    #[error("invalid webhook payload: {0}")]
+    InvalidWebhook(String),
 }
 
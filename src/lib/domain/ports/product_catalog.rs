@@ -0,0 +1,22 @@
+use std::future::Future;
+use thiserror::Error;
+use uuid::Uuid;
+use crate::domain::models::order_item::Currency;
+use crate::domain::models::product::Product;
+
+/// Source of truth for product names and prices.
+pub trait ProductCatalog: Clone + Send + Sync + 'static {
+    /// Looks up the products with the given ids, priced in `currency`. Products that
+    /// don't exist or have no price in `currency` are left out of the result.
+    fn find_products(
+        &self,
+        ids: &[Uuid],
+        currency: Currency,
+    ) -> impl Future<Output=Result<Vec<Product>, ProductCatalogError>> + Send;
+}
+
+#[derive(Error, Debug)]
+pub enum ProductCatalogError {
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
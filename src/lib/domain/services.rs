@@ -1,2 +1,3 @@
 pub mod order_service;
+pub mod outbox_relay;
 pub mod payment_service;
\ No newline at end of file
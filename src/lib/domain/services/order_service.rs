@@ -1,67 +1,118 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use anyhow::{anyhow, Error};
 use chrono::Utc;
 use stripe::Object;
 use uuid::Uuid;
-use crate::domain::models::order_details::{OrderDetails, SessionId, SessionStatus, UserName};
+use crate::domain::models::caller::Caller;
+use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId};
+use crate::domain::models::order_event::OrderEventKind;
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderPage};
 use crate::domain::models::order::{CreateOrderError, CreateOrderRequest, DeleteOrderError, FindOrderError, Order, UpdateOrderError, UpdateOrderStatusRequest};
-use crate::domain::models::order_item::OrderItem;
-use crate::domain::ports::checkout_producer::CheckoutProducer;
+use crate::domain::models::order_item::{CreateOrderItemRequest, OrderItem};
+use crate::domain::models::order_totals::PricingPolicy;
+use crate::domain::models::payment_event::PaymentEvent;
 use crate::domain::ports::order_repository::OrderRepository;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
+use crate::domain::models::product::Product;
+use crate::domain::ports::product_catalog::ProductCatalog;
+use crate::metrics::METRICS;
 
+/// Status changes are written to the outbox together with the order, the
+/// [`OutboxRelay`](crate::domain::services::outbox_relay::OutboxRelay) notifies the basket service.
+///
+/// Names and prices of ordered items always come from the [`ProductCatalog`], tax and
+/// shipping from the [`PricingPolicy`].
 #[derive(Debug, Clone)]
-pub struct DefaultOrderService<R, C, P>
+pub struct DefaultOrderService<R, P, C>
 where
     R: OrderRepository,
-    C: CheckoutProducer,
     P: PaymentService,
+    C: ProductCatalog,
 {
     repository: R,
-    checkout_producer: C,
-    payment_service: Arc<P>
+    payment_service: Arc<P>,
+    product_catalog: C,
+    pricing: PricingPolicy,
 }
 
-impl<R, C, P> DefaultOrderService<R, C, P>
+impl<R, P, C> DefaultOrderService<R, P, C>
 where
     R: OrderRepository,
-    C: CheckoutProducer,
     P: PaymentService,
+    C: ProductCatalog,
 {
 
-    pub fn new(repository: R, checkout_producer: C, payment_service: Arc<P>) -> Self {
+    pub const fn new(repository: R, payment_service: Arc<P>, product_catalog: C, pricing: PricingPolicy) -> Self {
         Self{
             repository,
-            checkout_producer,
-            payment_service
+            payment_service,
+            product_catalog,
+            pricing,
         }
     }
 }
 
+/// Prices the requested items with the catalog's products, rejecting unknown and
+/// inactive ones.
+fn resolve_items(
+    items: &[CreateOrderItemRequest],
+    products: &HashMap<Uuid, Product>,
+) -> Result<Vec<OrderItem>, CreateOrderError> {
+    items
+        .iter()
+        .map(|item| {
+            let product = products
+                .get(item.item_id())
+                .filter(|product| *product.active())
+                .ok_or(CreateOrderError::UnavailableProduct { item_id: *item.item_id() })?;
 
-impl<R, C, P> OrderService for DefaultOrderService<R, C, P>
+            Ok(OrderItem::new(
+                *item.id(),
+                product.name().clone(),
+                *item.item_id(),
+                product.price().clone(),
+                *item.quantity(),
+            ))
+        })
+        .collect()
+}
+
+/// Counts status changes that are published as order events.
+fn count_transition(to: OrderStatus) {
+    if let Some(kind) = OrderEventKind::for_status(to) {
+        METRICS.order_event(kind);
+    }
+}
+
+
+impl<R, P, C> OrderService for DefaultOrderService<R, P, C>
 where
      R: OrderRepository,
-     C: CheckoutProducer,
      P: PaymentService,
+     C: ProductCatalog,
  {
+     #[tracing::instrument(skip_all, fields(order_id = %req.id()))]
      async fn create_order(&self, req: &CreateOrderRequest) -> Result<String, CreateOrderError> {
-         let status = Some(SessionStatus::Open);
+         let status = OrderStatus::AwaitingPayment;
          let created_at = Utc::now();
 
-         let order_items = req.items()
-             .iter()
-             .map(|item| OrderItem::new(
-                                        item.id().clone(),
-                                        item.product_name().clone(), 
-                                        item.item_id().clone(),
-                                        item.price().clone())
-             )
+         let item_ids: Vec<Uuid> = req.items().iter().map(|item| *item.item_id()).collect();
+         let products: HashMap<Uuid, Product> = self.product_catalog
+             .find_products(&item_ids, *req.currency())
+             .await
+             .map_err(|e| CreateOrderError::Unknown(anyhow!(e)))?
+             .into_iter()
+             .map(|product| (*product.id(), product))
              .collect();
 
+         let order_items = resolve_items(req.items(), &products)?;
+         let totals = self.pricing.totals(&order_items, *req.currency())?;
+
          let checkout_session = self.payment_service
-             .create_checkout_session(&order_items)
+             .create_checkout_session(&order_items, self.pricing.shipping_fee(*req.currency()))
              .await
              .map_err(|e| {
                  CreateOrderError::Unknown(anyhow!(e))
@@ -74,6 +125,8 @@ where
              req.username().clone(),
              status,
              session_id,
+             *req.currency(),
+             totals,
              created_at,
          );
 
@@ -84,48 +137,211 @@ where
              .ok_or(CreateOrderError::Unknown(anyhow!("Couldn't get a checkout url")))?;
 
          let _ = self.repository.create_order(&order).await?;
-
-
+         METRICS.order_event(OrderEventKind::Created);
 
          Ok(checkout_url)
      }
+     #[tracing::instrument(skip_all, fields(session_id = %req))]
      async fn find_order_by_session_id(&self, req: &SessionId) -> Result<Order, FindOrderError> {
          self.repository.find_order_by_session_id(req).await
      }
 //
-     async fn find_orders_by_username(&self, req: &UserName) -> Result<Vec<Order>, FindOrderError> {
-         self.repository.find_orders_by_username(req).await
+     #[tracing::instrument(skip_all, fields(limit = req.limit()))]
+     async fn list_orders(&self, req: &ListOrdersRequest) -> Result<OrderPage, FindOrderError> {
+         self.repository.list_orders(req).await
      }
 
-     async fn find_order_by_id(&self, req: Uuid) -> Result<Order, FindOrderError> {
-         self.repository.find_order_by_id(req).await
+     #[tracing::instrument(skip_all, fields(order_id = %req))]
+     async fn find_order_by_id(&self, req: Uuid, caller: &Caller) -> Result<Order, FindOrderError> {
+         let order = self.repository.find_order_by_id(req).await?;
+         if !caller.can_access(order.details()) {
+             return Err(FindOrderError::Forbidden);
+         }
+
+         Ok(order)
      }
 
+     #[tracing::instrument(skip_all, fields(session_id = %req))]
      async fn notify_checkout_status(&self, req: &SessionId) -> Result<(), Error> {
          let order = self.repository.find_order_by_session_id(req).await?;
 
          let maybe_status = self.payment_service.retrieve_checkout_status(req).await?;
 
          if let Some(status) = maybe_status {
-             self.checkout_producer.notify_order_result(order.details().username(), &status).await?;
+             self.update_order_status(
+                 UpdateOrderStatusRequest::new(
+                     *order.details().order_id(),
+                     OrderStatus::from(status),
+                     Actor::StripeCheckout,
+                 )
+             ).await?;
              Ok(())
          } else { Err(anyhow!("Order doesn't have a checkout status")) }
      }
 
 
-     async fn delete_order(&self, req: Uuid) -> Result<Uuid, DeleteOrderError> {
-         self.repository.delete_order(req).await
+     #[tracing::instrument(skip_all, fields(order_id = %req))]
+     async fn delete_order(&self, req: Uuid, actor: &Actor) -> Result<Uuid, DeleteOrderError> {
+         self.repository.delete_order(req, actor).await
      }
 
+     #[tracing::instrument(skip_all)]
      async fn delete_all_orders(&self) -> Result<(), DeleteOrderError> {
          self.repository.delete_all_orders().await
          
      }
 
+     #[tracing::instrument(skip_all, fields(order_id = %req.id(), to = %req.status()))]
      async fn update_order_status(
          &self,
          req: UpdateOrderStatusRequest,
      ) -> Result<Order, UpdateOrderError> {
-         self.repository.update_order_status(req.id(), req.status().as_ref()).await
+         let mut order = self.repository
+             .find_order_by_id(*req.id())
+             .await
+             .map_err(|e| match e {
+                 FindOrderError::IdNotFound { .. } => UpdateOrderError::NotFound,
+                 e => UpdateOrderError::Unknown(anyhow!(e)),
+             })?;
+
+         let from = *order.details().status();
+         if !order.transition_to(*req.status())? {
+             return Ok(order);
+         }
+
+         let updated_order = self.repository.update_order_status(req.id(), &from, req.status(), req.actor()).await?;
+         count_transition(*req.status());
+
+         Ok(updated_order)
      }
- }
\ No newline at end of file
+
+     #[tracing::instrument(skip_all, fields(session_id = %req))]
+     async fn cancel_order(&self, req: &SessionId, caller: &Caller) -> Result<Order, UpdateOrderError> {
+         let mut order = self.repository
+             .find_order_by_session_id(req)
+             .await
+             .map_err(|e| match e {
+                 FindOrderError::SessionNotFound { .. } => UpdateOrderError::NotFound,
+                 e => UpdateOrderError::Unknown(anyhow!(e)),
+             })?;
+         if !caller.can_access(order.details()) {
+             return Err(UpdateOrderError::Forbidden);
+         }
+
+         let from = *order.details().status();
+         if !order.transition_to(OrderStatus::Cancelled)? {
+             return Ok(order);
+         }
+
+         self.payment_service
+             .expire_session(req)
+             .await
+             .map_err(|e| UpdateOrderError::Unknown(anyhow!(e)))?;
+
+         let cancelled_order = self.repository
+             .update_order_status(order.details().order_id(), &from, &OrderStatus::Cancelled, &caller.actor())
+             .await?;
+         count_transition(OrderStatus::Cancelled);
+
+         Ok(cancelled_order)
+     }
+
+     #[tracing::instrument(skip_all, fields(event_id = %event.id(), session_id = %event.session_id()))]
+     async fn handle_payment_event(&self, event: &PaymentEvent) -> Result<Order, UpdateOrderError> {
+         let order = self.repository
+             .find_order_by_session_id(event.session_id())
+             .await
+             .map_err(|e| match e {
+                 FindOrderError::SessionNotFound { .. } => UpdateOrderError::NotFound,
+                 e => UpdateOrderError::Unknown(anyhow!(e)),
+             })?;
+
+         let from = *order.details().status();
+         let to = event.kind().order_status();
+         match order.clone().transition_to(to) {
+             Ok(true) => {}
+             // Stripe doesn't order its events, a late or redundant one is acknowledged
+             // without touching the order so that it isn't redelivered forever
+             Ok(false) | Err(UpdateOrderError::InvalidTransition { .. }) => return Ok(order),
+             Err(e) => return Err(e),
+         }
+
+         let updated_order = self.repository
+             .apply_payment_event(event, order.details().order_id(), &from, &to)
+             .await?;
+
+         // replayed event, the basket was already notified
+         Ok(updated_order.map_or(order, |updated_order| {
+             count_transition(to);
+             updated_order
+         }))
+     }
+
+     #[tracing::instrument(skip_all, fields(order_id = %req))]
+     async fn find_status_history(&self, req: Uuid, caller: &Caller) -> Result<Vec<StatusChange>, FindOrderError> {
+         let history = self.repository.find_status_history(&req).await?;
+         if history.is_empty() {
+             return Err(FindOrderError::IdNotFound { id: req });
+         }
+         if !caller.can_access_history(&history) {
+             return Err(FindOrderError::Forbidden);
+         }
+
+         Ok(history)
+     }
+ }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use uuid::Uuid;
+    use crate::domain::models::order::CreateOrderError;
+    use crate::domain::models::order_item::{CreateOrderItemRequest, Currency, Price, ProductName, Quantity};
+    use crate::domain::models::product::Product;
+    use crate::domain::services::order_service::resolve_items;
+
+    fn catalog(products: Vec<Product>) -> HashMap<Uuid, Product> {
+        products.into_iter().map(|product| (*product.id(), product)).collect()
+    }
+
+    fn product(active: bool) -> Product {
+        Product::new(
+            Uuid::new_v4(),
+            ProductName::new("Monstera"),
+            Price::from_minor_units(2499, Currency::Eur).unwrap(),
+            active,
+        )
+    }
+
+    #[test]
+    fn resolve_items_uses_catalog_prices() {
+        let product = product(true);
+        let request = CreateOrderItemRequest::new(*product.id(), Quantity::new(2).unwrap());
+
+        let items = resolve_items(&[request], &catalog(vec![product.clone()])).unwrap();
+
+        assert_eq!(items[0].product_name(), product.name());
+        assert_eq!(items[0].price(), product.price());
+        assert_eq!(items[0].total().as_minor_units(), Some(4998));
+    }
+
+    #[test]
+    fn resolve_items_rejects_unknown_product() {
+        let item_id = Uuid::new_v4();
+        let request = CreateOrderItemRequest::new(item_id, Quantity::new(1).unwrap());
+
+        let result = resolve_items(&[request], &catalog(vec![product(true)]));
+
+        assert!(matches!(result, Err(CreateOrderError::UnavailableProduct { item_id: id }) if id == item_id));
+    }
+
+    #[test]
+    fn resolve_items_rejects_inactive_product() {
+        let product = product(false);
+        let request = CreateOrderItemRequest::new(*product.id(), Quantity::new(1).unwrap());
+
+        let result = resolve_items(&[request], &catalog(vec![product]));
+
+        assert!(matches!(result, Err(CreateOrderError::UnavailableProduct { .. })));
+    }
+}
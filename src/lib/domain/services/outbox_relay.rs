@@ -0,0 +1,220 @@
+use std::time::Duration;
+use crate::domain::models::outbox::OutboxMessage;
+use tracing::Instrument;
+use crate::domain::ports::checkout_producer::{CheckoutProducer, NotifyError};
+use crate::domain::ports::outbox_repository::{OutboxError, OutboxRepository};
+use crate::telemetry::{in_trace, TraceContext};
+
+const BATCH_SIZE: u32 = 50;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const LEASE: Duration = Duration::from_secs(30);
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Drains the outbox into the [`CheckoutProducer`], giving at-least-once delivery
+/// of order events independent of the broker being up during a request.
+#[derive(Debug, Clone)]
+pub struct OutboxRelay<O, C>
+where
+    O: OutboxRepository,
+    C: CheckoutProducer,
+{
+    outbox: O,
+    checkout_producer: C,
+}
+
+impl<O, C> OutboxRelay<O, C>
+where
+    O: OutboxRepository,
+    C: CheckoutProducer,
+{
+    pub const fn new(outbox: O, checkout_producer: C) -> Self {
+        Self { outbox, checkout_producer }
+    }
+
+    /// Relays pending messages until the task is dropped.
+    pub async fn run(self) {
+        loop {
+            match self.relay_batch().await {
+                Ok(relayed) if relayed > 0 => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("failed to relay outbox: {e:?}"),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Publishes one batch of due messages and returns how many were delivered.
+    ///
+    /// # Errors
+    /// [`OutboxError`] if the outbox can't be read or updated. Failed deliveries are
+    /// rescheduled instead.
+    pub async fn relay_batch(&self) -> Result<usize, OutboxError> {
+        let messages = self.outbox.claim_pending(BATCH_SIZE, LEASE).await?;
+        let mut delivered = 0;
+
+        for message in messages {
+            match self.publish(&message).await {
+                Ok(()) => {
+                    self.outbox.mark_delivered(message.id()).await?;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "failed to deliver outbox message {} (attempt {}): {e}",
+                        message.id(),
+                        message.attempts() + 1
+                    );
+                    self.outbox
+                        .record_failure(message.id(), &e.to_string(), backoff(&message))
+                        .await?;
+                }
+            }
+        }
+
+        Ok(delivered)
+    }
+}
+
+impl<O, C> OutboxRelay<O, C>
+where
+    O: OutboxRepository,
+    C: CheckoutProducer,
+{
+    /// Publishes in the trace of the operation that enqueued the message.
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), NotifyError> {
+        let trace = message
+            .traceparent()
+            .as_deref()
+            .and_then(|traceparent| traceparent.parse::<TraceContext>().ok())
+            .map_or_else(TraceContext::new_root, |parent| parent.child());
+        let span = tracing::info_span!(
+            "relay_outbox_message",
+            event_id = %message.id(),
+            attempt = message.attempts() + 1,
+            trace_id = %trace.trace_id(),
+            span_id = %trace.span_id(),
+        );
+
+        in_trace(trace, self.checkout_producer.publish_order_event(message.event()))
+            .instrument(span)
+            .await
+    }
+}
+
+fn backoff(message: &OutboxMessage) -> Duration {
+    let exponent = (*message.attempts()).min(16);
+    BASE_BACKOFF.saturating_mul(2u32.pow(exponent)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use anyhow::anyhow;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+    use crate::domain::models::order::Order;
+    use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+    use crate::domain::models::order_event::{OrderEvent, OrderEventKind};
+    use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+    use crate::domain::models::order_totals::OrderTotals;
+    use crate::domain::models::outbox::OutboxMessage;
+    use crate::domain::ports::checkout_producer::{CheckoutProducer, NotifyError};
+    use crate::domain::ports::outbox_repository::{OutboxError, OutboxRepository};
+    use crate::domain::services::outbox_relay::{backoff, OutboxRelay};
+
+    #[derive(Clone, Default)]
+    struct FakeOutbox {
+        pending: Arc<Mutex<Vec<OutboxMessage>>>,
+        delivered: Arc<Mutex<Vec<Uuid>>>,
+        failed: Arc<Mutex<Vec<(Uuid, Duration)>>>,
+    }
+
+    impl OutboxRepository for FakeOutbox {
+        async fn claim_pending(&self, _limit: u32, _lease: Duration) -> Result<Vec<OutboxMessage>, OutboxError> {
+            Ok(self.pending.lock().unwrap().drain(..).collect())
+        }
+
+        async fn mark_delivered(&self, id: &Uuid) -> Result<(), OutboxError> {
+            self.delivered.lock().unwrap().push(*id);
+            Ok(())
+        }
+
+        async fn record_failure(&self, id: &Uuid, _error: &str, retry_in: Duration) -> Result<(), OutboxError> {
+            self.failed.lock().unwrap().push((*id, retry_in));
+            Ok(())
+        }
+    }
+
+    #[derive(Clone)]
+    struct FakeProducer {
+        broker_up: bool,
+    }
+
+    impl CheckoutProducer for FakeProducer {
+        async fn publish_order_event(&self, _event: &OrderEvent) -> Result<(), NotifyError> {
+            if self.broker_up {
+                Ok(())
+            } else {
+                Err(NotifyError::UnknownError(anyhow!("broker unreachable")))
+            }
+        }
+    }
+
+    fn create_message(attempts: u32) -> OutboxMessage {
+        let item = OrderItem::new(
+            Uuid::new_v4(),
+            ProductName::new("Monstera"),
+            Uuid::new_v4(),
+            Price::from_minor_units(1999, Currency::Eur).unwrap(),
+            Quantity::new(1).unwrap(),
+        );
+        let details = OrderDetails::new(
+            Uuid::new_v4(),
+            UserName::new("Hannes"),
+            OrderStatus::Paid,
+            SessionId::new("cs_test_123"),
+            Currency::Eur,
+            OrderTotals::new(Decimal::new(1999, 2), Decimal::new(319, 2), Decimal::ZERO, Decimal::new(1999, 2)),
+            Utc::now(),
+        );
+        let order = Order::new(details, vec![item]).unwrap();
+
+        OutboxMessage::new(OrderEvent::new(Uuid::new_v4(), OrderEventKind::Paid, order, Utc::now()), attempts, None)
+    }
+
+    #[tokio::test]
+    async fn relay_marks_published_messages_delivered() {
+        let outbox = FakeOutbox::default();
+        let message = create_message(0);
+        outbox.pending.lock().unwrap().push(message.clone());
+        let relay = OutboxRelay::new(outbox.clone(), FakeProducer { broker_up: true });
+
+        let delivered = relay.relay_batch().await.unwrap();
+
+        assert_eq!(delivered, 1);
+        assert_eq!(*outbox.delivered.lock().unwrap(), vec![*message.id()]);
+    }
+
+    #[tokio::test]
+    async fn relay_schedules_retry_when_broker_is_down() {
+        let outbox = FakeOutbox::default();
+        let message = create_message(2);
+        outbox.pending.lock().unwrap().push(message.clone());
+        let relay = OutboxRelay::new(outbox.clone(), FakeProducer { broker_up: false });
+
+        let delivered = relay.relay_batch().await.unwrap();
+
+        assert_eq!(delivered, 0);
+        assert!(outbox.delivered.lock().unwrap().is_empty());
+        assert_eq!(*outbox.failed.lock().unwrap(), vec![(*message.id(), Duration::from_secs(4))]);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff(&create_message(0)), Duration::from_secs(1));
+        assert_eq!(backoff(&create_message(40)), Duration::from_secs(300));
+    }
+}
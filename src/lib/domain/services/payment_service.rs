@@ -1,30 +1,80 @@
 use crate::domain::models::order_details::{SessionId, SessionStatus};
 use crate::domain::ports::payment_service::{PaymentService, PaymentServiceError};
+use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
 use anyhow::anyhow;
-use stripe::{CheckoutSession, CheckoutSessionBillingAddressCollection, CheckoutSessionId, CheckoutSessionMode, CheckoutSessionStatus, Client, CreateCheckoutSession, CreateCheckoutSessionLineItems, CreateCheckoutSessionLineItemsPriceData, CreateCheckoutSessionLineItemsPriceDataProductData, CreateCheckoutSessionPaymentMethodTypes, CreateCheckoutSessionShippingAddressCollection, CreateCheckoutSessionShippingAddressCollectionAllowedCountries, Currency, StripeError};
-use crate::domain::models::order_item::OrderItem;
+use stripe::{CheckoutSession, CheckoutSessionBillingAddressCollection, CheckoutSessionId, CheckoutSessionMode, CheckoutSessionPaymentStatus, CheckoutSessionStatus, Client, CreateCheckoutSession, CreateCheckoutSessionLineItems, CreateCheckoutSessionLineItemsPriceData, CreateCheckoutSessionLineItemsPriceDataProductData, CreateCheckoutSessionPaymentMethodTypes, CreateCheckoutSessionShippingAddressCollection, CreateCheckoutSessionShippingAddressCollectionAllowedCountries, CreateCheckoutSessionShippingOptions, CreateCheckoutSessionShippingOptionsShippingRateData, CreateCheckoutSessionShippingOptionsShippingRateDataFixedAmount, CreateCheckoutSessionShippingOptionsShippingRateDataType, Currency, EventObject, EventType, StripeError, Webhook};
+use crate::domain::models::order_item::{self, OrderItem, Price};
+use crate::domain::models::payment_event::{PaymentEvent, PaymentEventId, PaymentEventKind};
+use crate::metrics::METRICS;
+use crate::telemetry::REDACTED;
+
+/// The Stripe account payments go to and where customers are sent back to afterwards.
+#[derive(Clone, PartialEq, Eq)]
+pub struct StripeConfig {
+    pub secret_key: String,
+    pub webhook_secret: String,
+    /// Base url of our payment API, Stripe redirects to its `/success` and `/cancel`.
+    pub redirect_url: String,
+    pub shipping_countries: Vec<ShippingCountry>,
+}
+
+impl Debug for StripeConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StripeConfig")
+            .field("secret_key", &REDACTED)
+            .field("webhook_secret", &REDACTED)
+            .field("redirect_url", &self.redirect_url)
+            .field("shipping_countries", &self.shipping_countries)
+            .finish()
+    }
+}
 
 #[derive(Clone)]
 pub struct StripeService {
     client: Client,
     redirect_url: String,
+    webhook_secret: String,
+    shipping_countries: Vec<CreateCheckoutSessionShippingAddressCollectionAllowedCountries>,
 }
 
 impl StripeService {
-    pub fn new(secret: String, redirect_url: String) -> Self {
+    #[must_use]
+    pub fn new(secret: String, redirect_url: String, webhook_secret: String) -> Self {
         let client = Client::new(secret);
-        
-        Self { client, redirect_url }
+        let shipping_countries = vec![
+            CreateCheckoutSessionShippingAddressCollectionAllowedCountries::De,
+            CreateCheckoutSessionShippingAddressCollectionAllowedCountries::Us,
+        ];
+
+        Self { client, redirect_url, webhook_secret, shipping_countries }
+    }
+
+    /// Countries customers can have their order shipped to, Germany and the US by default.
+    #[must_use]
+    pub fn with_shipping_countries(mut self, countries: &[ShippingCountry]) -> Self {
+        self.shipping_countries = countries.iter().map(|country| country.0).collect();
+        self
+    }
+}
+
+/// An ISO 3166-1 alpha-2 country code that Stripe can collect shipping addresses for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShippingCountry(CreateCheckoutSessionShippingAddressCollectionAllowedCountries);
+
+impl FromStr for ShippingCountry {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(code.to_ascii_uppercase()))
+            .map(Self)
+            .map_err(|_| format!("unsupported shipping country {code}"))
     }
 }
 
 impl PaymentService for StripeService {
-    async fn create_checkout_session(&self, order_items: &Vec<OrderItem>) -> Result<CheckoutSession, PaymentServiceError> {
-        let allowed_countries = vec![
-            CreateCheckoutSessionShippingAddressCollectionAllowedCountries::De,
-            CreateCheckoutSessionShippingAddressCollectionAllowedCountries::Us,
-        ];
+    #[tracing::instrument(skip_all, fields(items = order_items.len()))]
+    async fn create_checkout_session(&self, order_items: &Vec<OrderItem>, shipping: Option<&Price>) -> Result<CheckoutSession, PaymentServiceError> {
         let success_url = format!("{}/success?session_id={{CHECKOUT_SESSION_ID}}", self.redirect_url);
         let cancel_url = format!("{}/cancel?session_id={{CHECKOUT_SESSION_ID}}", self.redirect_url);
 
@@ -32,33 +82,39 @@ impl PaymentService for StripeService {
             .iter()
             .map(|item| item.to_stripe_line_item())
             .collect();
+        let shipping_options = shipping
+            .map(to_stripe_shipping_option)
+            .transpose()?
+            .map(|option| vec![option]);
 
         let params = CreateCheckoutSession {
             billing_address_collection: Some(CheckoutSessionBillingAddressCollection::Required),
             shipping_address_collection: Some(CreateCheckoutSessionShippingAddressCollection {
-                allowed_countries,
+                allowed_countries: self.shipping_countries.clone(),
             }),
             payment_method_types: Some(vec![CreateCheckoutSessionPaymentMethodTypes::Card]),
             mode: Some(CheckoutSessionMode::Payment),
             success_url: Some(success_url.as_str()),
             cancel_url: Some(cancel_url.as_str()),
             line_items: Some(line_items),
+            shipping_options,
             ..Default::default()
         };
 
-        CheckoutSession::create(&self.client, params)
+        METRICS.stripe_call("create_checkout_session", CheckoutSession::create(&self.client, params))
             .await
             .map_err(PaymentServiceError::from)
 
     }
 
+    #[tracing::instrument(skip_all, fields(session_id = %id))]
     async fn retrieve_checkout_status(&self, id: &SessionId) -> Result<Option<SessionStatus>, PaymentServiceError> {
         let session_id = CheckoutSessionId::from_str(&id.to_string())
             .map_err(|e| {
                 PaymentServiceError::InvalidSessionId(id.clone())
             })?;
 
-        let checkout_session = CheckoutSession::retrieve(&self.client, &session_id, &[])
+        let checkout_session = METRICS.stripe_call("retrieve_checkout_session", CheckoutSession::retrieve(&self.client, &session_id, &[]))
             .await
             .map_err(|e| {
                 PaymentServiceError::Unknown(anyhow!(e).context(format!(
@@ -72,12 +128,13 @@ impl PaymentService for StripeService {
         Ok(status)
     }
 
+    #[tracing::instrument(skip_all, fields(session_id = %id))]
     async fn expire_session(&self, id: &SessionId) -> Result<(), PaymentServiceError> {
         let session_id = CheckoutSessionId::from_str(&id.to_string())
             .map_err(|e| {
                 PaymentServiceError::InvalidSessionId(id.clone())
             })?;
-        let _ = CheckoutSession::expire(&self.client, &session_id).await.map_err(|e| {
+        let _ = METRICS.stripe_call("expire_checkout_session", CheckoutSession::expire(&self.client, &session_id)).await.map_err(|e| {
             PaymentServiceError::Unknown(anyhow!(e).context(format!(
                 "Failed to expire checkout session with id {}",
                 id
@@ -86,6 +143,44 @@ impl PaymentService for StripeService {
         
         Ok(())
     }
+
+    #[tracing::instrument(skip_all)]
+    fn parse_webhook_event(&self, payload: &str, signature: &str) -> Result<Option<PaymentEvent>, PaymentServiceError> {
+        let event = Webhook::construct_event(payload, signature, &self.webhook_secret)
+            .map_err(|e| PaymentServiceError::InvalidWebhook(e.to_string()))?;
+
+        if !matches!(
+            event.type_,
+            EventType::CheckoutSessionCompleted
+                | EventType::CheckoutSessionExpired
+                | EventType::CheckoutSessionAsyncPaymentSucceeded
+                | EventType::CheckoutSessionAsyncPaymentFailed
+        ) {
+            return Ok(None);
+        }
+
+        let EventObject::CheckoutSession(session) = event.data.object else {
+            return Err(PaymentServiceError::InvalidWebhook(format!(
+                "event {} of type {} does not carry a checkout session",
+                event.id, event.type_
+            )));
+        };
+
+        let kind = match event.type_ {
+            EventType::CheckoutSessionCompleted => PaymentEventKind::CheckoutCompleted {
+                paid: session.payment_status != CheckoutSessionPaymentStatus::Unpaid,
+            },
+            EventType::CheckoutSessionExpired => PaymentEventKind::CheckoutExpired,
+            EventType::CheckoutSessionAsyncPaymentSucceeded => PaymentEventKind::AsyncPaymentSucceeded,
+            _ => PaymentEventKind::AsyncPaymentFailed,
+        };
+
+        Ok(Some(PaymentEvent::new(
+            PaymentEventId::new(event.id.as_str()),
+            SessionId::new(session.id.as_str()),
+            kind,
+        )))
+    }
 }
 
 impl From<CheckoutSessionStatus> for SessionStatus {
@@ -101,11 +196,11 @@ impl From<CheckoutSessionStatus> for SessionStatus {
 impl OrderItem {
     fn to_stripe_line_item(&self) -> CreateCheckoutSessionLineItems {
         CreateCheckoutSessionLineItems {
-            quantity: Some(1),
+            quantity: Some(u64::from(self.quantity().value())),
             price_data: Some(CreateCheckoutSessionLineItemsPriceData {
-                currency: Currency::EUR,
+                currency: to_stripe_currency(*self.price().currency()),
 
-                unit_amount: self.price().as_cents(),
+                unit_amount: self.price().as_minor_units(),
                 product_data: Some(CreateCheckoutSessionLineItemsPriceDataProductData {
                     name: self.product_name().to_string(),
                     ..Default::default()
@@ -117,6 +212,36 @@ impl OrderItem {
     }
 }
 
+fn to_stripe_shipping_option(fee: &Price) -> Result<CreateCheckoutSessionShippingOptions, PaymentServiceError> {
+    let amount = fee
+        .as_minor_units()
+        .ok_or_else(|| anyhow!("shipping fee {fee} doesn't fit into minor units"))?;
+
+    Ok(CreateCheckoutSessionShippingOptions {
+        shipping_rate_data: Some(CreateCheckoutSessionShippingOptionsShippingRateData {
+            display_name: "Standard".to_string(),
+            fixed_amount: Some(CreateCheckoutSessionShippingOptionsShippingRateDataFixedAmount {
+                amount,
+                currency: to_stripe_currency(*fee.currency()),
+                currency_options: None,
+            }),
+            type_: Some(CreateCheckoutSessionShippingOptionsShippingRateDataType::FixedAmount),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+const fn to_stripe_currency(currency: order_item::Currency) -> Currency {
+    match currency {
+        order_item::Currency::Eur => Currency::EUR,
+        order_item::Currency::Usd => Currency::USD,
+        order_item::Currency::Gbp => Currency::GBP,
+        order_item::Currency::Chf => Currency::CHF,
+        order_item::Currency::Jpy => Currency::JPY,
+    }
+}
+
 impl From<StripeError> for PaymentServiceError {
     fn from(err: StripeError) -> Self {
         PaymentServiceError::Unknown(anyhow!(
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use futures::future::{join_all, BoxFuture};
+use futures::FutureExt;
+use getset::Getters;
+use serde::Serialize;
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Readiness probes usually give up after a second, a slow dependency counts as down.
+const READINESS_TIMEOUT: Duration = Duration::from_millis(800);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyState {
+    Up,
+    Down,
+}
+
+/// Calls `connect` until it succeeds, waiting twice as long after every failure
+/// up to a minute. The server keeps serving meanwhile, requests that need the
+/// dependency fail until it is back.
+pub async fn connect_with_backoff<T, E, F, Fut>(dependency: &str, mut connect: F) -> T
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut backoff = BASE_BACKOFF;
+
+    loop {
+        match connect().await {
+            Ok(connected) => {
+                tracing::info!("connected to {dependency}");
+                return connected;
+            }
+            Err(e) => {
+                tracing::warn!("{dependency} is unavailable, retrying in {}s: {e}", backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Runs `check` every ten seconds until the task is dropped, retrying with backoff
+/// while it fails. `check` should reconnect if it can.
+pub async fn monitor<E, F, Fut>(dependency: &str, mut check: F)
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    loop {
+        connect_with_backoff(dependency, &mut check).await;
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = check().await {
+                tracing::warn!("lost connection to {dependency}: {e}");
+                break;
+            }
+        }
+    }
+}
+
+type Probe = Arc<dyn Fn() -> BoxFuture<'static, Result<(), HealthCheckError>> + Send + Sync>;
+
+/// The dependencies checked before the service is reported ready for traffic.
+#[derive(Clone, Default)]
+pub struct Readiness {
+    checks: Vec<Check>,
+}
+
+#[derive(Clone)]
+struct Check {
+    dependency: String,
+    critical: bool,
+    probe: Probe,
+}
+
+/// Result of checking one dependency.
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct DependencyReport {
+    state: DependencyState,
+    critical: bool,
+    error: Option<String>,
+}
+
+impl Readiness {
+    /// Adds a dependency the service can't serve requests without.
+    #[must_use]
+    pub fn critical(self, dependency: &str, check: impl HealthCheck) -> Self {
+        self.with_check(dependency, true, check)
+    }
+
+    /// Adds a dependency the service can do without for a while, e.g. because its
+    /// work is queued until it is back.
+    #[must_use]
+    pub fn optional(self, dependency: &str, check: impl HealthCheck) -> Self {
+        self.with_check(dependency, false, check)
+    }
+
+    fn with_check(mut self, dependency: &str, critical: bool, check: impl HealthCheck) -> Self {
+        let probe: Probe = Arc::new(move || {
+            let check = check.clone();
+            async move { check.check().await }.boxed()
+        });
+        self.checks.push(Check { dependency: dependency.to_string(), critical, probe });
+        self
+    }
+
+    /// Checks all dependencies concurrently.
+    pub async fn check(&self) -> BTreeMap<String, DependencyReport> {
+        let reports = self.checks.iter().map(|check| async move {
+            let error = match tokio::time::timeout(READINESS_TIMEOUT, (check.probe)()).await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some(format!("no answer within {}ms", READINESS_TIMEOUT.as_millis())),
+            };
+            let state = if error.is_some() { DependencyState::Down } else { DependencyState::Up };
+
+            (check.dependency.clone(), DependencyReport { state, critical: check.critical, error })
+        });
+
+        join_all(reports).await.into_iter().collect()
+    }
+}
+
+/// `true` unless a critical dependency is down.
+#[must_use]
+pub fn is_ready(reports: &BTreeMap<String, DependencyReport>) -> bool {
+    reports.values().all(|report| !report.critical || report.state == DependencyState::Up)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+    use crate::health::{connect_with_backoff, is_ready, DependencyState, Readiness};
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_until_connected() {
+        let attempts = AtomicU32::new(0);
+
+        let connected = connect_with_backoff("broker", || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err("connection refused"),
+                attempt => Ok(attempt),
+            }
+        })
+            .await;
+
+        assert_eq!(connected, 2);
+    }
+
+    #[derive(Clone)]
+    struct FakeCheck {
+        up: bool,
+    }
+
+    impl HealthCheck for FakeCheck {
+        async fn check(&self) -> Result<(), HealthCheckError> {
+            if self.up {
+                Ok(())
+            } else {
+                Err(HealthCheckError::Unavailable("connection refused".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn ready_unless_a_critical_dependency_is_down() {
+        let readiness = Readiness::default()
+            .critical("database", FakeCheck { up: true })
+            .optional("broker", FakeCheck { up: false });
+
+        let reports = readiness.check().await;
+
+        assert!(is_ready(&reports));
+        assert_eq!(reports["broker"].state(), &DependencyState::Down);
+        assert_eq!(reports["broker"].error().as_deref(), Some("connection refused"));
+
+        let reports = readiness.critical("keycloak", FakeCheck { up: false }).check().await;
+
+        assert!(!is_ready(&reports));
+    }
+}
@@ -1,82 +1,95 @@
-use std::collections::HashMap;
 use std::sync::Arc;
 use actix_web::web;
 use actix_web::web::{Data, ServiceConfig};
 use anyhow::Context;
-use jsonwebtoken::{DecodingKey, Validation};
-use utoipa::OpenApi;
+use utoipa::{Modify, OpenApi};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
 use utoipa_swagger_ui::SwaggerUi;
+use crate::domain::models::order_item::Currency;
+use crate::health::Readiness;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
-use crate::domain::services::order_service::DefaultOrderService;
-use crate::domain::services::payment_service::StripeService;
+use crate::inbound::http::authorization::oidc::TokenVerifier;
 use crate::inbound::http::handlers::cancel::cancel;
-use crate::inbound::http::handlers::create_checkout::{create_checkout, CreateOrderHttpRequestBody};
+use crate::inbound::http::handlers::create_checkout::{create_checkout, CreateOrderHttpRequestBody, CreateOrderItemHttpRequestBody};
 use crate::inbound::http::handlers::delete_all_orders::delete_all_orders;
 use crate::inbound::http::handlers::delete_by_id::delete_order_by_id;
 use crate::inbound::http::handlers::get_by_id::get_order_by_id;
+use crate::inbound::http::handlers::get_status_history::get_status_history;
 use crate::inbound::http::handlers::success::success;
-use crate::outbound::postgres::Postgres;
-use crate::outbound::rabbitmq::RabbitMQ;
+use crate::inbound::http::handlers::webhook::webhook;
 use crate::inbound::http::handlers::create_checkout::__path_create_checkout;
 use crate::inbound::http::handlers::cancel::__path_cancel;
 use crate::inbound::http::handlers::delete_all_orders::__path_delete_all_orders;
 use crate::inbound::http::handlers::delete_by_id::__path_delete_order_by_id;
 use crate::inbound::http::handlers::get_all_orders_for_user::get_all_orders_for_user;
 use crate::inbound::http::handlers::get_by_id::__path_get_order_by_id;
+use crate::inbound::http::handlers::get_status_history::__path_get_status_history;
 use crate::inbound::http::handlers::success::__path_success;
 use crate::inbound::http::handlers::get_all_orders_for_user::__path_get_all_orders_for_user;
-use crate::inbound::http::responses::OrderResponseData;
+use crate::inbound::http::handlers::webhook::__path_webhook;
+use crate::inbound::http::handlers::health::{live, ready, DependencyResponseData, LivenessResponseData, ReadinessResponseData};
+use crate::inbound::http::handlers::health::{__path_live, __path_ready};
+use crate::inbound::http::handlers::metrics::{metrics, record_request};
+use crate::inbound::http::handlers::metrics::__path_metrics;
+use crate::inbound::http::request_tracing::trace_request;
+use crate::inbound::http::responses::{CheckoutDetailsResponse, OrderItemResponse, OrderPageResponseData, OrderResponseData, StatusChangeResponseData};
 mod handlers;
 mod responses;
 mod extractors;
+mod request_tracing;
 pub mod authorization;
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct HttpServerConfig<'a> {
-    pub port: &'a str,
+pub struct HttpServerConfig {
+    pub port: u16,
+    /// Orders that don't name a currency are charged in this one.
+    pub default_currency: Currency,
 }
 
 #[derive(Clone, Debug)]
 pub struct AppState<OS: OrderService, PS: PaymentService> {
     order_service: Arc<OS>,
     payment_service: Arc<PS>,
+    default_currency: Currency,
 }
 
 pub struct AuthState {
-    auth_keys: Arc<HashMap<String,DecodingKey>>,
-    validator: Arc<Validation>,
+    verifier: TokenVerifier,
 }
 pub struct HttpServer;
 
 impl HttpServer {
 
-    pub async fn new(
-        order_service: impl OrderService,
-        payment_service: Arc<impl PaymentService>,
-        auth_key: HashMap<String,DecodingKey>,
-        validator: Validation,
-        config: &HttpServerConfig<'_>,
+    /// # Errors
+    /// If the server can't bind to the configured port or stops with an error.
+    pub async fn new<OS: OrderService, PS: PaymentService>(
+        order_service: OS,
+        payment_service: Arc<PS>,
+        verifier: TokenVerifier,
+        readiness: Readiness,
+        config: &HttpServerConfig,
     ) -> anyhow::Result<()> {
-
-        env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
         let app_state = Data::new(AppState {
             order_service: Arc::new(order_service),
             payment_service,
+            default_currency: config.default_currency,
         });
 
         let openapi = ApiDoc::openapi();
 
-        let auth_state = Data::new(AuthState {
-            auth_keys: Arc::new(auth_key),
-            validator: Arc::new(validator),
-        });
+        let auth_state = Data::new(AuthState { verifier });
+        let readiness = Data::new(readiness);
         actix_web::HttpServer::new(move || {
             actix_web::App::new()
-                .wrap(actix_web::middleware::Logger::default())
+                .wrap(actix_web::middleware::from_fn(record_request))
+                .wrap(actix_web::middleware::from_fn(trace_request))
                 .app_data(app_state.clone())
                 .app_data(auth_state.clone())
-                .configure(api_routes)
+                .app_data(readiness.clone())
+                .configure(api_routes::<OS, PS>)
                 .service(
                     SwaggerUi::new("/swagger-ui/{_:.*}")
                         .url("/api-docs/openapi.json", openapi.clone())
@@ -90,18 +103,24 @@ impl HttpServer {
     }
 }
 
-fn api_routes(cfg: &mut ServiceConfig) {
-    type OrderService = DefaultOrderService<Postgres, RabbitMQ, StripeService>;
-    type PaymentService = StripeService;
+fn api_routes<OS: OrderService, PS: PaymentService>(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/health")
+            .route("/live", web::get().to(live))
+            .route("/ready", web::get().to(ready))
+    );
+    cfg.route("/metrics", web::get().to(metrics));
     cfg.service(
         web::scope("/api/payment")
-            .route("/create-checkout-session", web::post().to(create_checkout::<OrderService, PaymentService>))
-            .route("/success", web::get().to(success::<OrderService, PaymentService>))
-            .route("/cancel", web::get().to(cancel::<OrderService, PaymentService>))
-            .route("/orderbyid", web::get().to(get_order_by_id::<OrderService, PaymentService>))
-            .route("/allordersforuser", web::get().to(get_all_orders_for_user::<OrderService, PaymentService>))
-            .route("/order", web::delete().to(delete_order_by_id::<OrderService, PaymentService>))
-            .route("/orders", web::delete().to(delete_all_orders::<OrderService, PaymentService>))
+            .route("/create-checkout-session", web::post().to(create_checkout::<OS, PS>))
+            .route("/success", web::get().to(success::<OS, PS>))
+            .route("/cancel", web::get().to(cancel::<OS, PS>))
+            .route("/orderbyid", web::get().to(get_order_by_id::<OS, PS>))
+            .route("/allordersforuser", web::get().to(get_all_orders_for_user::<OS, PS>))
+            .route("/order/{id}/history", web::get().to(get_status_history::<OS, PS>))
+            .route("/order", web::delete().to(delete_order_by_id::<OS, PS>))
+            .route("/orders", web::delete().to(delete_all_orders::<OS, PS>))
+            .route("/webhook", web::post().to(webhook::<OS, PS>))
     );
 }
 
@@ -114,13 +133,41 @@ fn api_routes(cfg: &mut ServiceConfig) {
         delete_order_by_id,
         get_all_orders_for_user,
         get_order_by_id,
+        get_status_history,
         success,
+        webhook,
+        live,
+        ready,
+        metrics,
     ),
     components(
         schemas(
             CreateOrderHttpRequestBody,
-            OrderResponseData
+            CreateOrderItemHttpRequestBody,
+            OrderResponseData,
+            OrderPageResponseData,
+            OrderItemResponse,
+            CheckoutDetailsResponse,
+            StatusChangeResponseData,
+            LivenessResponseData,
+            ReadinessResponseData,
+            DependencyResponseData
         )
-    )
+    ),
+    modifiers(&BearerAuth)
 )]
-pub struct ApiDoc;
\ No newline at end of file
+pub struct ApiDoc;
+
+/// Keycloak access tokens, required roles are listed as scopes of the operation.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        let scheme = Http::builder()
+            .scheme(HttpAuthScheme::Bearer)
+            .bearer_format("JWT")
+            .build();
+        components.add_security_scheme("bearer_auth", SecurityScheme::Http(scheme));
+    }
+}
\ No newline at end of file
@@ -1 +1,2 @@
-pub mod keycloak;
\ No newline at end of file
+pub mod jwks;
+pub mod oidc;
\ No newline at end of file
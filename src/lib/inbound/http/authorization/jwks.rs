@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use chrono::DateTime;
+use jsonwebtoken::jwk::{JwkSet, KeyAlgorithm, PublicKeyUse};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use reqwest::header::{HeaderMap, CACHE_CONTROL, EXPIRES};
+use reqwest::Client;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+use crate::telemetry::{TraceContext, TRACEPARENT};
+
+/// Used when the JWKS response carries no cache headers.
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+const MIN_TTL: Duration = Duration::from_secs(30);
+const MAX_TTL: Duration = Duration::from_secs(3600);
+/// Unknown `kid`s trigger a refresh at most this often by default, so that tokens with
+/// made up key ids can't make us hammer Keycloak.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A signing key of the identity provider together with the algorithm it is used with.
+#[derive(Clone)]
+pub struct VerificationKey {
+    pub key: DecodingKey,
+    pub algorithm: Algorithm,
+}
+
+#[derive(Debug, Error)]
+pub enum JwksError {
+    #[error("no signing key with id {0}")]
+    UnknownKeyId(String),
+    #[error("failed to fetch JWKS: {0}")]
+    Fetch(#[from] reqwest::Error),
+}
+
+struct CachedKeys {
+    keys: HashMap<String, VerificationKey>,
+    fetched_at: Option<Instant>,
+    expires_at: Instant,
+}
+
+/// The identity provider's signing keys, refreshed when the cache headers of the JWKS
+/// say so and whenever a token names a key we don't know, so that key rotations
+/// don't need a restart.
+#[derive(Clone)]
+pub struct JwksCache {
+    client: Client,
+    jwks_uri: String,
+    keys: Arc<RwLock<CachedKeys>>,
+    refreshing: Arc<Mutex<()>>,
+    min_refresh_interval: Duration,
+    allowed_algorithms: Vec<Algorithm>,
+}
+
+impl JwksCache {
+    /// Starts empty, call [`refresh`](Self::refresh) or [`run`](Self::run) to load the keys.
+    #[must_use]
+    pub fn new(jwks_uri: &str) -> Self {
+        Self {
+            client: Client::new(),
+            jwks_uri: jwks_uri.to_string(),
+            keys: Arc::new(RwLock::new(CachedKeys {
+                keys: HashMap::new(),
+                fetched_at: None,
+                expires_at: Instant::now(),
+            })),
+            refreshing: Arc::new(Mutex::new(())),
+            min_refresh_interval: MIN_REFRESH_INTERVAL,
+            allowed_algorithms: Vec::new(),
+        }
+    }
+
+    /// How long unknown key ids wait for the next refresh, 10 seconds by default.
+    #[must_use]
+    pub const fn with_min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    /// Only keeps keys for these algorithms, all asymmetric ones if empty.
+    #[must_use]
+    pub fn with_allowed_algorithms(mut self, algorithms: Vec<Algorithm>) -> Self {
+        self.allowed_algorithms = algorithms;
+        self
+    }
+
+    /// Looks up the key `kid`, refreshing the key set first if it is stale or doesn't
+    /// contain `kid` yet.
+    ///
+    /// # Errors
+    /// [`JwksError::UnknownKeyId`] if the key set has no key `kid`, even after a refresh.
+    pub async fn key(&self, kid: &str) -> Result<VerificationKey, JwksError> {
+        if let Some(key) = self.cached(kid) {
+            return Ok(key);
+        }
+
+        let may_refresh = self.read().fetched_at.is_none_or(|at| at.elapsed() >= self.min_refresh_interval);
+        if may_refresh {
+            // stale keys are better than none while Keycloak is unreachable
+            if let Err(e) = self.refresh().await {
+                tracing::warn!("failed to refresh JWKS: {e}");
+            }
+        }
+
+        self.read()
+            .keys
+            .get(kid)
+            .cloned()
+            .ok_or_else(|| JwksError::UnknownKeyId(kid.to_string()))
+    }
+
+    /// Fetches the key set. Concurrent callers share one request.
+    ///
+    /// # Errors
+    /// [`JwksError::Fetch`] if the key set can't be downloaded or parsed.
+    ///
+    /// # Panics
+    /// If another thread panicked while holding the key lock.
+    pub async fn refresh(&self) -> Result<(), JwksError> {
+        let started = Instant::now();
+        let _guard = self.refreshing.lock().await;
+        if self.read().fetched_at.is_some_and(|at| at >= started) {
+            return Ok(());
+        }
+
+        let mut request = self.client.get(&self.jwks_uri);
+        if let Some(traceparent) = TraceContext::outgoing() {
+            request = request.header(TRACEPARENT, traceparent);
+        }
+        let response = request
+            .send()
+            .await?
+            .error_for_status()?;
+        let ttl = ttl(response.headers());
+        let jwk_set: JwkSet = response.json().await?;
+
+        let keys = verification_keys(&jwk_set, &self.allowed_algorithms);
+        tracing::info!("loaded {} signing keys from {}", keys.len(), self.jwks_uri);
+
+        let now = Instant::now();
+        *self.keys.write().expect("JWKS lock poisoned") = CachedKeys {
+            keys,
+            fetched_at: Some(now),
+            expires_at: now + ttl,
+        };
+
+        Ok(())
+    }
+
+    /// Keeps the key set fresh until the task is dropped.
+    pub async fn run(self) {
+        loop {
+            let next_refresh = match self.refresh().await {
+                Ok(()) => self.read().expires_at,
+                Err(e) => {
+                    tracing::error!("failed to refresh JWKS: {e}");
+                    Instant::now() + RETRY_INTERVAL
+                }
+            };
+            tokio::time::sleep_until(next_refresh).await;
+        }
+    }
+
+    fn cached(&self, kid: &str) -> Option<VerificationKey> {
+        let cached = self.read();
+        if cached.expires_at <= Instant::now() {
+            return None;
+        }
+
+        cached.keys.get(kid).cloned()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, CachedKeys> {
+        self.keys.read().expect("JWKS lock poisoned")
+    }
+
+    /// A cache that never refreshes, for tests that sign their own tokens.
+    #[cfg(test)]
+    pub(crate) fn with_keys(keys: HashMap<String, VerificationKey>) -> Self {
+        let cache = Self::new("http://127.0.0.1:0");
+        let now = Instant::now();
+        *cache.keys.write().expect("JWKS lock poisoned") = CachedKeys {
+            keys,
+            fetched_at: Some(now),
+            expires_at: now + MAX_TTL,
+        };
+
+        cache
+    }
+}
+
+impl HealthCheck for JwksCache {
+    /// Expired keys still count, they are used until a refresh succeeds.
+    async fn check(&self) -> Result<(), HealthCheckError> {
+        if self.read().keys.is_empty() {
+            return Err(HealthCheckError::Unavailable(format!("no signing keys loaded from {}", self.jwks_uri)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Signing keys of the set that we can verify tokens with. Encryption keys, keys
+/// without an asymmetric signing algorithm and keys for algorithms that aren't
+/// `allowed` are skipped.
+fn verification_keys(jwk_set: &JwkSet, allowed: &[Algorithm]) -> HashMap<String, VerificationKey> {
+    jwk_set
+        .keys
+        .iter()
+        .filter(|jwk| !matches!(jwk.common.public_key_use, Some(PublicKeyUse::Encryption)))
+        .filter_map(|jwk| {
+            let kid = jwk.common.key_id.clone()?;
+            let algorithm = jwk.common.key_algorithm
+                .and_then(signing_algorithm)
+                .filter(|algorithm| allowed.is_empty() || allowed.contains(algorithm))?;
+            match DecodingKey::from_jwk(jwk) {
+                Ok(key) => Some((kid, VerificationKey { key, algorithm })),
+                Err(e) => {
+                    tracing::warn!("skipping signing key {kid}: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+const fn signing_algorithm(algorithm: KeyAlgorithm) -> Option<Algorithm> {
+    match algorithm {
+        KeyAlgorithm::RS256 => Some(Algorithm::RS256),
+        KeyAlgorithm::RS384 => Some(Algorithm::RS384),
+        KeyAlgorithm::RS512 => Some(Algorithm::RS512),
+        KeyAlgorithm::PS256 => Some(Algorithm::PS256),
+        KeyAlgorithm::PS384 => Some(Algorithm::PS384),
+        KeyAlgorithm::PS512 => Some(Algorithm::PS512),
+        KeyAlgorithm::ES256 => Some(Algorithm::ES256),
+        KeyAlgorithm::ES384 => Some(Algorithm::ES384),
+        KeyAlgorithm::EdDSA => Some(Algorithm::EdDSA),
+        // shared secrets and encryption algorithms can't come from a public key set
+        KeyAlgorithm::HS256
+        | KeyAlgorithm::HS384
+        | KeyAlgorithm::HS512
+        | KeyAlgorithm::RSA1_5
+        | KeyAlgorithm::RSA_OAEP
+        | KeyAlgorithm::RSA_OAEP_256 => None,
+    }
+}
+
+/// How long the response may be cached according to `Cache-Control: max-age` or
+/// `Expires`, clamped so that we neither poll Keycloak constantly nor miss a
+/// rotation for hours.
+fn ttl(headers: &HeaderMap) -> Duration {
+    let cache_control = headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let directives: Vec<&str> = cache_control.split(',').map(str::trim).collect();
+
+    let ttl = if directives.iter().any(|d| d.eq_ignore_ascii_case("no-store") || d.eq_ignore_ascii_case("no-cache")) {
+        Some(MIN_TTL)
+    } else if let Some(max_age) = directives
+        .iter()
+        .find_map(|d| d.strip_prefix("max-age="))
+        .and_then(|seconds| seconds.parse().ok())
+    {
+        Some(Duration::from_secs(max_age))
+    } else {
+        headers
+            .get(EXPIRES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .map(|expires| (expires.to_utc() - chrono::Utc::now()).to_std().unwrap_or_default())
+    };
+
+    ttl.unwrap_or(DEFAULT_TTL).clamp(MIN_TTL, MAX_TTL)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use reqwest::header::{HeaderMap, HeaderValue, CACHE_CONTROL, EXPIRES};
+    use crate::inbound::http::authorization::jwks::{ttl, DEFAULT_TTL, MAX_TTL, MIN_TTL};
+
+    fn headers(pairs: &[(reqwest::header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn ttl_from_max_age() {
+        let ttl = ttl(&headers(&[(CACHE_CONTROL, "public, max-age=600")]));
+
+        assert_eq!(ttl, Duration::from_secs(600));
+    }
+
+    #[test]
+    fn ttl_is_clamped() {
+        assert_eq!(ttl(&headers(&[(CACHE_CONTROL, "max-age=1")])), MIN_TTL);
+        assert_eq!(ttl(&headers(&[(CACHE_CONTROL, "max-age=604800")])), MAX_TTL);
+        assert_eq!(ttl(&headers(&[(CACHE_CONTROL, "no-cache")])), MIN_TTL);
+    }
+
+    #[test]
+    fn ttl_without_cache_headers() {
+        assert_eq!(ttl(&HeaderMap::new()), DEFAULT_TTL);
+        assert_eq!(ttl(&headers(&[(EXPIRES, "Wed, 21 Oct 2015 07:28:00 GMT")])), MIN_TTL);
+    }
+}
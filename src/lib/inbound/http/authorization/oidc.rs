@@ -0,0 +1,140 @@
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use getset::Getters;
+use jsonwebtoken::{Algorithm, Validation};
+use reqwest::Client;
+use serde::Deserialize;
+use thiserror::Error;
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+use crate::inbound::http::authorization::jwks::JwksCache;
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// The parts of an `OpenID` provider's discovery document we need to verify its tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct ProviderMetadata {
+    issuer: String,
+    jwks_uri: String,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+impl ProviderMetadata {
+    /// The advertised signing algorithms we can verify, unknown ones are ignored.
+    #[must_use]
+    pub fn signing_algorithms(&self) -> Vec<Algorithm> {
+        self.id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|alg| Algorithm::from_str(alg).ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    #[error("failed to fetch the OpenID configuration: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("OpenID configuration is for issuer {found}, expected {expected}")]
+    IssuerMismatch { expected: String, found: String },
+}
+
+/// What the service accepts as a valid access token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Base URL of the provider, e.g. `https://keycloak.example.com/realms/shop`.
+    pub issuer: String,
+    /// Tokens have to be issued for one of these, not checked if empty.
+    pub audience: Vec<String>,
+    /// Registered claims a token must contain, e.g. `exp`, `iss`, `aud` or `sub`.
+    pub required_claims: Vec<String>,
+}
+
+impl OidcConfig {
+    /// Reads the provider's discovery document and sets up verification of its tokens.
+    ///
+    /// # Errors
+    /// See [`fetch_metadata`].
+    pub async fn discover(&self) -> Result<(JwksCache, Validation), DiscoveryError> {
+        let metadata = fetch_metadata(&self.issuer).await?;
+        let keys = JwksCache::new(metadata.jwks_uri())
+            .with_allowed_algorithms(metadata.signing_algorithms());
+
+        Ok((keys, self.validation(&metadata)))
+    }
+
+    /// The algorithm is replaced by the one of the verifying key for every token.
+    #[must_use]
+    pub fn validation(&self, metadata: &ProviderMetadata) -> Validation {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[metadata.issuer()]);
+        if self.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&self.audience);
+        }
+        validation.set_required_spec_claims(&self.required_claims);
+
+        validation
+    }
+}
+
+/// The signing keys and token validation of the provider, available once its discovery
+/// document could be read. The server starts without them, tokens are refused until then.
+#[derive(Clone, Default)]
+pub struct TokenVerifier {
+    discovered: Arc<OnceLock<(JwksCache, Validation)>>,
+}
+
+impl TokenVerifier {
+    #[must_use]
+    pub fn discovered(keys: JwksCache, validation: Validation) -> Self {
+        let verifier = Self::default();
+        verifier.set(keys, validation);
+        verifier
+    }
+
+    /// Only the first discovery counts.
+    pub fn set(&self, keys: JwksCache, validation: Validation) {
+        let _ = self.discovered.set((keys, validation));
+    }
+
+    #[must_use]
+    pub fn get(&self) -> Option<&(JwksCache, Validation)> {
+        self.discovered.get()
+    }
+}
+
+impl HealthCheck for TokenVerifier {
+    async fn check(&self) -> Result<(), HealthCheckError> {
+        match self.get() {
+            Some((keys, _)) => keys.check().await,
+            None => Err(HealthCheckError::Unavailable("OpenID configuration not discovered yet".to_string())),
+        }
+    }
+}
+
+/// Fetches `{issuer}/.well-known/openid-configuration` and makes sure it describes `issuer`.
+///
+/// # Errors
+/// [`DiscoveryError::Fetch`] if the document can't be downloaded or parsed and
+/// [`DiscoveryError::IssuerMismatch`] if it is for another issuer.
+pub async fn fetch_metadata(issuer: &str) -> Result<ProviderMetadata, DiscoveryError> {
+    let issuer = issuer.trim_end_matches('/');
+    let metadata: ProviderMetadata = Client::new()
+        .get(format!("{issuer}{DISCOVERY_PATH}"))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if metadata.issuer.trim_end_matches('/') != issuer {
+        return Err(DiscoveryError::IssuerMismatch {
+            expected: issuer.to_string(),
+            found: metadata.issuer,
+        });
+    }
+
+    Ok(metadata)
+}
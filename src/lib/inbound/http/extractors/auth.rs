@@ -1,68 +1,335 @@
-use std::sync::Arc;
+use std::marker::PhantomData;
 use actix_web::{web, Error, FromRequest, HttpRequest};
 use actix_web::dev::Payload;
-use futures::future::{ready, Ready};
+use futures::future::LocalBoxFuture;
 use getset::Getters;
-use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use jsonwebtoken::{decode, decode_header};
 use serde::{Deserialize, Serialize};
+use crate::domain::models::caller::Caller;
+use crate::domain::models::order_details::UserName;
 use crate::inbound::http::AuthState;
+use crate::inbound::http::handlers::ApiError;
 
 #[derive(Debug, Serialize, Deserialize, Getters)]
 #[getset(get = "pub")]
 pub struct Claims {
     exp: usize,
     preferred_username: String,
+    /// Keycloak specific, other providers don't put roles into the token.
+    #[serde(default)]
     realm_access: RealmAccess,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Claims {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.realm_access.roles.iter().any(|r| r == role)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Getters)]
+#[getset(get = "pub")]
 pub struct RealmAccess {
     roles: Vec<String>,
 }
 
+/// Keycloak names the users behind client credentials tokens like this.
+const SERVICE_ACCOUNT_PREFIX: &str = "service-account-";
+
+/// A machine client authenticated with the client credentials grant.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct ServiceClaims {
+    client_id: String,
+    scopes: Vec<String>,
+}
+
+impl ServiceClaims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Everything we read from an access token, before knowing whom it was issued to.
+#[derive(Debug, Deserialize)]
+struct TokenClaims {
+    exp: usize,
+    preferred_username: Option<String>,
+    #[serde(default)]
+    realm_access: RealmAccess,
+    client_id: Option<String>,
+    azp: Option<String>,
+    #[serde(default)]
+    scope: String,
+}
+
+impl TokenClaims {
+    /// Only Keycloak's `client_id` claim or a service account name make a token a
+    /// client's, `azp` is set on user tokens as well.
+    fn into_principal(self) -> Option<Principal> {
+        let service_account = self.preferred_username
+            .as_deref()
+            .is_some_and(|username| username.starts_with(SERVICE_ACCOUNT_PREFIX));
+
+        if self.client_id.is_some() || service_account {
+            return self.client_id.or(self.azp).map(|client_id| Principal::Service(ServiceClaims {
+                client_id,
+                scopes: self.scope.split_whitespace().map(ToString::to_string).collect(),
+            }));
+        }
+
+        self.preferred_username.map(|username| Principal::User(Claims {
+            exp: self.exp,
+            preferred_username: username,
+            realm_access: self.realm_access,
+        }))
+    }
+}
+
+/// Whom a verified access token was issued to, a user or a machine client.
 #[derive(Debug)]
-pub struct KeycloakToken(Claims);
+pub enum Principal {
+    User(Claims),
+    Service(ServiceClaims),
+}
 
-impl KeycloakToken {
-    pub fn claims(&self) -> &Claims {
-        &self.0
+impl Principal {
+    pub fn caller(&self) -> Caller {
+        match self {
+            Self::User(claims) => user_caller(claims),
+            Self::Service(claims) => Caller::service(claims.client_id()),
+        }
     }
 }
 
-impl FromRequest for KeycloakToken {
+impl FromRequest for Principal {
     type Error = Error;
-    type Future = Ready<Result<Self, Self::Error>>;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let auth_state = req.app_data::<web::Data<AuthState>>().unwrap();
-        let validation = Arc::clone(&auth_state.validator);
+        let auth_state = req.app_data::<web::Data<AuthState>>().unwrap().clone();
         let auth_header = req
             .headers()
             .get("Authorization")
             .and_then(|h| h.to_str().ok())
-            .and_then(|h| h.strip_prefix("Bearer "));
-
-        if let Some(token) = auth_header {
-
-            match decode_header(token) {
-                Ok(header) => {
-                    if let Some(kid) = header.kid {
-
-                        if let Some(decoding_key) = auth_state.auth_keys.get(&kid) {
-                            let result  = decode::<Claims>(token, decoding_key, &validation);
-                            return match result {
-                                Ok(token_data) => ready(Ok(KeycloakToken(token_data.claims))),
-                                Err(_) => ready(Err(actix_web::error::ErrorUnauthorized("Invalid token"))),
-                            };
-                        }
-                    }
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(ToString::to_string);
+
+        Box::pin(async move {
+            let token = auth_header
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Missing or invalid Authorization header"))?;
+            let kid = decode_header(&token)
+                .ok()
+                .and_then(|header| header.kid)
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid JWT header"))?;
+            let (keys, validation) = auth_state.verifier
+                .get()
+                .ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Keycloak has not been reached yet"))?;
+            let verification_key = keys
+                .key(&kid)
+                .await
+                .map_err(|_| actix_web::error::ErrorUnauthorized("Unknown signing key"))?;
+
+            // the key decides the algorithm, not the token
+            let mut validation = validation.clone();
+            validation.algorithms = vec![verification_key.algorithm];
+
+            decode::<TokenClaims>(&token, &verification_key.key, &validation)
+                .map_err(|_| actix_web::error::ErrorUnauthorized("Invalid token"))?
+                .claims
+                .into_principal()
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Token names neither a user nor a client"))
+        })
+    }
+}
+
+/// A token issued to a user, machine clients are rejected with 403.
+#[derive(Debug)]
+pub struct KeycloakToken(Claims);
+
+impl KeycloakToken {
+    pub const fn claims(&self) -> &Claims {
+        &self.0
+    }
+
+    pub fn caller(&self) -> Caller {
+        user_caller(&self.0)
+    }
+}
+
+impl FromRequest for KeycloakToken {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let principal = Principal::from_request(req, payload);
+
+        Box::pin(async move {
+            match principal.await? {
+                Principal::User(claims) => Ok(Self(claims)),
+                Principal::Service(_) => Err(ApiError::Forbidden("requires a user token".to_string()).into()),
+            }
+        })
+    }
+}
+
+fn user_caller(claims: &Claims) -> Caller {
+    Caller::user(UserName::new(claims.preferred_username()), claims.has_role(Admin::NAME))
+}
+
+/// A Keycloak realm role that [`RequireRole`] can check for.
+pub trait Role {
+    const NAME: &'static str;
+}
+
+#[derive(Debug)]
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// A [`KeycloakToken`] whose realm roles contain `R`, rejects the request with
+/// 403 otherwise.
+#[derive(Debug)]
+pub struct RequireRole<R: Role> {
+    token: KeycloakToken,
+    role: PhantomData<R>,
+}
+
+impl<R: Role> RequireRole<R> {
+    pub fn claims(&self) -> &Claims {
+        self.token.claims()
+    }
+}
+
+impl<R: Role> FromRequest for RequireRole<R> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let token = KeycloakToken::from_request(req, payload);
+
+        Box::pin(async move {
+            let token = token.await?;
+            if token.claims().has_role(R::NAME) {
+                Ok(Self { token, role: PhantomData })
+            } else {
+                Err(ApiError::Forbidden(format!("requires role {}", R::NAME)).into())
+            }
+        })
+    }
+}
+
+/// An `OAuth2` scope that [`RequireScope`] can check for.
+pub trait Scope {
+    const NAME: &'static str;
+}
+
+#[derive(Debug)]
+pub struct OrdersRead;
+
+impl Scope for OrdersRead {
+    const NAME: &'static str = "orders:read";
+}
+
+/// A user, or a machine client whose token grants the scope `S`. Users aren't checked
+/// for `S`, the order service only lets them at their own orders.
+#[derive(Debug)]
+pub struct RequireScope<S: Scope> {
+    principal: Principal,
+    scope: PhantomData<S>,
+}
+
+impl<S: Scope> RequireScope<S> {
+    pub fn caller(&self) -> Caller {
+        self.principal.caller()
+    }
+}
+
+impl<S: Scope> FromRequest for RequireScope<S> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let principal = Principal::from_request(req, payload);
+
+        Box::pin(async move {
+            match principal.await? {
+                Principal::Service(claims) if !claims.has_scope(S::NAME) => {
+                    Err(ApiError::Forbidden(format!("requires scope {}", S::NAME)).into())
                 }
-                Err(err) => {
-                    return ready(Err(actix_web::error::ErrorUnauthorized("Invalid JWT header")))
-                },
+                principal => Ok(Self { principal, scope: PhantomData }),
             }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::inbound::http::extractors::auth::{Claims, Principal, RealmAccess, TokenClaims};
+
+    fn claims_with_roles(roles: &[&str]) -> Claims {
+        Claims {
+            exp: 0,
+            preferred_username: "Hannes".to_string(),
+            realm_access: RealmAccess { roles: roles.iter().map(ToString::to_string).collect() },
         }
+    }
+
+    #[test]
+    fn has_role() {
+        let claims = claims_with_roles(&["user", "admin"]);
+
+        assert!(claims.has_role("admin"));
+        assert!(!claims.has_role("Admin"));
+    }
+
+    #[test]
+    fn has_no_roles() {
+        assert!(!claims_with_roles(&[]).has_role("admin"));
+    }
 
-        ready(Err(actix_web::error::ErrorUnauthorized("Missing or invalid Authorization header")))
+    fn principal(claims: serde_json::Value) -> Option<Principal> {
+        serde_json::from_value::<TokenClaims>(claims).unwrap().into_principal()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn user_token() {
+        let principal = principal(json!({ "exp": 0, "preferred_username": "Hannes", "azp": "frontend" }));
+
+        assert!(matches!(principal, Some(Principal::User(claims)) if claims.preferred_username() == "Hannes"));
+    }
+
+    #[test]
+    fn client_credentials_token() {
+        let principal = principal(json!({
+            "exp": 0,
+            "preferred_username": "service-account-basket",
+            "azp": "basket",
+            "scope": "profile orders:read",
+        }));
+
+        assert!(matches!(
+            principal,
+            Some(Principal::Service(claims)) if claims.client_id() == "basket" && claims.has_scope("orders:read")
+        ));
+    }
+
+    #[test]
+    fn client_id_makes_a_service_token() {
+        let principal = principal(json!({ "exp": 0, "client_id": "fulfilment", "azp": "fulfilment" }));
+
+        assert!(matches!(principal, Some(Principal::Service(claims)) if claims.client_id() == "fulfilment"));
+    }
+
+    #[test]
+    fn user_token_without_username() {
+        assert!(principal(json!({ "exp": 0, "azp": "frontend", "scope": "orders:read" })).is_none());
+    }
+
+    #[test]
+    fn token_without_subject() {
+        assert!(principal(json!({ "exp": 0 })).is_none());
+    }
+}
@@ -6,15 +6,20 @@ use serde::Serialize;
 use thiserror::Error;
 use utoipa::ToSchema;
 use crate::domain::models::order::{DeleteOrderError, FindOrderError, UpdateOrderError};
+use crate::domain::models::order_page::ListOrdersError;
 use crate::domain::ports::payment_service::PaymentServiceError;
 
 pub mod create_checkout;
 pub mod success;
 pub mod cancel;
 pub mod get_by_id;
+pub mod get_status_history;
 pub mod get_all_orders_for_user;
 pub mod delete_by_id;
 pub mod delete_all_orders;
+pub mod webhook;
+pub mod health;
+pub mod metrics;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ApiErrorData {
@@ -59,10 +64,17 @@ pub enum ApiError {
     UnprocessableEntity(String),
     #[error("Couldn't find {0}")]
     NotFound(String),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
 }
 
 impl From<anyhow::Error> for ApiError {
     fn from(e: anyhow::Error) -> Self {
+        tracing::error!(error = %format_args!("{e:#}"), "request failed");
         Self::InternalServerError(e.to_string())
     }
 }
@@ -73,20 +85,40 @@ impl From<FindOrderError> for ApiError {
             FindOrderError::IdNotFound { id } => {
                 Self::NotFound(format!("Order ID not found: {id}"))
             }
-            FindOrderError::Unknown(_) => {
+            FindOrderError::SessionNotFound { session_id } => {
+                Self::NotFound(format!("Order for session not found: {session_id}"))
+            }
+            e @ FindOrderError::Forbidden => {
+                Self::Forbidden(e.to_string())
+            }
+            FindOrderError::Unknown(e) => {
+                tracing::error!(error = %format_args!("{e:#}"), "failed to find order");
                 Self::InternalServerError("Internal server error".to_string())
             }
         }
     }
 }
 
+impl From<ListOrdersError> for ApiError {
+    fn from(e: ListOrdersError) -> Self {
+        Self::BadRequest(e.to_string())
+    }
+}
+
 impl From<UpdateOrderError> for ApiError {
     fn from(e: UpdateOrderError) -> Self {
         match e {
             UpdateOrderError::NotFound => {
                 Self::NotFound("Order not found".to_string())
             }
-            UpdateOrderError::Unknown(_) => {
+            e @ (UpdateOrderError::InvalidTransition { .. } | UpdateOrderError::Conflict) => {
+                Self::Conflict(e.to_string())
+            }
+            e @ UpdateOrderError::Forbidden => {
+                Self::Forbidden(e.to_string())
+            }
+            UpdateOrderError::Unknown(e) => {
+                tracing::error!(error = %format_args!("{e:#}"), "failed to update order");
                 Self::InternalServerError("Internal server error".to_string()) 
             }
         }
@@ -96,19 +128,24 @@ impl From<UpdateOrderError> for ApiError {
 impl From<PaymentServiceError> for ApiError {
     fn from(e: PaymentServiceError) -> Self {
         match e {
-            PaymentServiceError::Unknown(_) => {
+            PaymentServiceError::Unknown(e) => {
+                tracing::error!(error = %format_args!("{e:#}"), "payment provider call failed");
                 Self::InternalServerError("Internal server error while processing payment status"
                     .to_string())
             }
             PaymentServiceError::InvalidSessionId(id) => {
                 Self::NotFound(format!("Invalid session ID: {id}"))
             }
+            PaymentServiceError::InvalidWebhook(reason) => {
+                Self::BadRequest(reason)
+            }
         }
     }
 }
 
 impl From<DeleteOrderError> for ApiError {
     fn from(e: DeleteOrderError) -> Self {
+        tracing::error!(error = %format_args!("{e:#}"), "failed to delete order");
         ApiError::InternalServerError("Internal server error".to_string()) 
     }
 }
@@ -119,6 +156,9 @@ impl ResponseError for ApiError {
             Self::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
         }
     }
 
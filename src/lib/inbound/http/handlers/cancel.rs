@@ -28,37 +28,23 @@ impl CancelHttpRequestQuery {
     CancelHttpRequestQuery,
   ),
   responses(
-    (status = 200, description = "ID of canceled order", body = Uuid)
-  )
+    (status = 200, description = "ID of canceled order", body = Uuid),
+    (status = 403, description = "Order belongs to another user"),
+    (status = 409, description = "Order can no longer be canceled")
+  ),
+  security(("bearer_auth" = []))
 )]
 pub async fn cancel<OS: OrderService, PS: PaymentService>(
     auth: KeycloakToken,
     state: Data<AppState<OS, PS>>,
     query: Query<CancelHttpRequestQuery>
 ) -> Result<impl Responder, ApiError> {
-
-    println!("{auth:?}");
     let domain_req = query.into_inner().into_domain();
-    let order = state
-        .order_service
-        .find_order_by_session_id(&domain_req)
-        .await
-        .map_err(ApiError::from)?;
 
-    let session_id = order.details().session_id();
-    state
-        .payment_service
-        .expire_session(session_id)
-        .await
-        .map_err(ApiError::from)?;
-
-    let order_id = order.details().order_id();
     state
         .order_service
-        .delete_order(*order_id)
+        .cancel_order(&domain_req, &auth.caller())
         .await
         .map_err(ApiError::from)
-        .map(|id | ApiResponseBody::new(StatusCode::OK, id))
-
-
+        .map(|order| ApiResponseBody::new(StatusCode::OK, *order.details().order_id()))
 }
\ No newline at end of file
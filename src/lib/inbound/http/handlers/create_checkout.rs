@@ -8,7 +8,7 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 use crate::domain::models::order::{CreateOrderError, CreateOrderRequest};
 use crate::domain::models::order_details::UserName;
-use crate::domain::models::order_item::{CreateOrderItemRequest, Price, PriceError, ProductName};
+use crate::domain::models::order_item::{CreateOrderItemRequest, Currency, CurrencyError, Quantity, QuantityError};
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
@@ -18,39 +18,48 @@ use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateOrderHttpRequestBody {
+    /// ISO 4217 code the order is charged in, the shop's default currency if omitted
+    #[schema(example = "USD")]
+    currency: Option<String>,
     items: Vec<CreateOrderItemHttpRequestBody>,
 }
 
+/// Name and price of the product are looked up server-side.
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateOrderItemHttpRequestBody {
-    name: String,
-    item_price: f64,
     plant_id: Uuid,
+    #[serde(default = "default_quantity")]
+    #[schema(default = 1, minimum = 1, maximum = 999)]
+    quantity: u32,
 }
 
-impl TryFrom<CreateOrderItemHttpRequestBody> for CreateOrderItemRequest {
-    type Error = PriceError;
+const fn default_quantity() -> u32 {
+    1
+}
 
-    fn try_from(item: CreateOrderItemHttpRequestBody) -> Result<Self, Self::Error> {
-        let price = Price::new(item.item_price)?;
-        let product_name = ProductName::new(&item.name);
+impl CreateOrderItemHttpRequestBody {
+    fn try_into_domain(self) -> Result<CreateOrderItemRequest, ParseCreateOrderHttpRequestError> {
+        let quantity = Quantity::new(self.quantity)?;
 
-        Ok(Self::new(product_name, item.plant_id, price))
+        Ok(CreateOrderItemRequest::new(self.plant_id, quantity))
     }
 }
 
 #[derive(Debug, Clone, Error)]
-enum ParseCreateOrderHttpRequestError {
+pub enum ParseCreateOrderHttpRequestError {
+    #[error(transparent)]
+    Quantity(#[from] QuantityError),
     #[error(transparent)]
-    Price(#[from] PriceError),
+    Currency(#[from] CurrencyError),
 }
 
 impl From<ParseCreateOrderHttpRequestError> for ApiError {
     fn from(e: ParseCreateOrderHttpRequestError) -> Self {
         let message = match e {
-            ParseCreateOrderHttpRequestError::Price(e) =>
-                "Price sent is invalid.".to_string(),
+            ParseCreateOrderHttpRequestError::Quantity(e) =>
+                format!("Quantity sent is invalid: {e}"),
+            ParseCreateOrderHttpRequestError::Currency(e) => e.to_string(),
         };
 
         Self::UnprocessableEntity(message)
@@ -63,8 +72,13 @@ impl From<CreateOrderError> for ApiError {
             CreateOrderError::NoItems => {
                 Self::UnprocessableEntity("No items were supplied".to_string())
             }
+            e @ (CreateOrderError::CurrencyMismatch { .. }
+            | CreateOrderError::UnavailableProduct { .. }
+            | CreateOrderError::TotalTooLarge { .. }) => {
+                Self::UnprocessableEntity(e.to_string())
+            }
             CreateOrderError::Unknown(e) => {
-                println!("{}", e);
+                tracing::error!(error = %format_args!("{e:#}"), "failed to create order");
                 Self::UnprocessableEntity("Internal server error".to_string())
             }
         }
@@ -72,16 +86,17 @@ impl From<CreateOrderError> for ApiError {
 }
 
 impl CreateOrderHttpRequestBody {
-    fn try_into_domain(self, token: &KeycloakToken) -> Result<CreateOrderRequest, ParseCreateOrderHttpRequestError> {
+    fn try_into_domain(self, token: &KeycloakToken, default_currency: Currency) -> Result<CreateOrderRequest, ParseCreateOrderHttpRequestError> {
         let username = UserName::new(token.claims().preferred_username());
+        let currency = self.currency.map_or(Ok(default_currency), |currency| currency.parse())?;
         let items = self
             .items
             .into_iter()
-            .map(CreateOrderItemRequest::try_from)
+            .map(CreateOrderItemHttpRequestBody::try_into_domain)
             .collect::<Result<_, _>>()?;
 
 
-        Ok(CreateOrderRequest::new(username, items))
+        Ok(CreateOrderRequest::new(username, currency, items))
     }
 }
 #[utoipa::path(
@@ -89,16 +104,17 @@ impl CreateOrderHttpRequestBody {
   path="/api/payment/create-checkout-session",
   request_body=CreateOrderHttpRequestBody,
   responses(
-    (status = 201, description = "Successfully created session", body = String)
-  )
+    (status = 201, description = "Successfully created session", body = String),
+    (status = 422, description = "Invalid request or unknown product")
+  ),
+  security(("bearer_auth" = []))
 )]
 pub async fn create_checkout<OS: OrderService, PS: PaymentService>(
     state: web::Data<AppState<OS, PS>>,
     body: Json<CreateOrderHttpRequestBody>,
     token: KeycloakToken,
 ) -> Result<ApiResponseBody<String>, ApiError> {
-    println!("{body:#?}");
-    let domain_req = body.into_inner().try_into_domain(&token)?;
+    let domain_req = body.into_inner().try_into_domain(&token, state.default_currency)?;
 
     state
         .order_service
@@ -4,16 +4,21 @@ use actix_web::web::Data;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
+use crate::inbound::http::extractors::auth::{Admin, RequireRole};
 use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
 
 #[utoipa::path(
   delete,
   path="/api/payment/orders",
   responses(
-    (status = 200, description = "Successfully deleted all order")
-  )
+    (status = 200, description = "Successfully deleted all order"),
+    (status = 401, description = "Missing or invalid token"),
+    (status = 403, description = "Caller is not an admin")
+  ),
+  security(("bearer_auth" = ["admin"]))
 )]
 pub async fn delete_all_orders<OS: OrderService, PS: PaymentService>(
+    _: RequireRole<Admin>,
     state: Data<AppState<OS, PS>>,
 ) -> Result<impl Responder, ApiError> {
     state
@@ -4,9 +4,12 @@ use actix_web::web::{Data, Query};
 use serde::Deserialize;
 use utoipa::IntoParams;
 use uuid::Uuid;
+use crate::domain::models::order_details::UserName;
+use crate::domain::models::order_history::Actor;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
+use crate::inbound::http::extractors::auth::{Admin, RequireRole};
 use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
 
 #[derive(Debug, Clone, Deserialize, IntoParams)]
@@ -28,18 +31,23 @@ impl DeleteByOrderIdHttpRequestQuery {
     ),
     path="/api/payment/order",
     responses(
-    (status = 200, description = "Successfully deleted order")
-    )
+    (status = 200, description = "Successfully deleted order"),
+    (status = 401, description = "Missing or invalid token"),
+    (status = 403, description = "Caller is not an admin")
+    ),
+    security(("bearer_auth" = ["admin"]))
 )]
 pub async fn delete_order_by_id<OS: OrderService, PS: PaymentService>(
+    token: RequireRole<Admin>,
     state: Data<AppState<OS, PS>>,
     query: Query<DeleteByOrderIdHttpRequestQuery>
 ) -> Result<impl Responder, ApiError> {
     let domain_req = query.into_inner().into_domain();
+    let actor = Actor::User(UserName::new(token.claims().preferred_username()));
 
     state
         .order_service
-        .delete_order(domain_req)
+        .delete_order(domain_req, &actor)
         .await
         .map_err(ApiError::from)
         .map(|_| ApiResponseBody::new(StatusCode::OK, ()))
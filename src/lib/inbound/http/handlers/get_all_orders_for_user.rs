@@ -1,40 +1,91 @@
 use actix_web::http::StatusCode;
 use actix_web::Responder;
-use actix_web::web::Data;
-use crate::domain::models::order_details::UserName;
+use actix_web::web::{Data, Query};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use crate::domain::models::order_details::{OrderStatus, UserName};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderCursor, OrderFilter, SortOrder};
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
 use crate::inbound::http::extractors::auth::KeycloakToken;
 use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
-use crate::inbound::http::responses::OrderResponseData;
+use crate::inbound::http::responses::OrderPageResponseData;
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    /// Newest first
+    #[default]
+    Desc,
+    /// Oldest first
+    Asc,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct ListOrdersHttpRequestQuery {
+    /// Comma separated, e.g. `paid,awaiting_payment`. All statuses if omitted.
+    status: Option<String>,
+    /// Only orders created at or after this time
+    created_from: Option<DateTime<Utc>>,
+    /// Only orders created before this time
+    created_before: Option<DateTime<Utc>>,
+    /// By `createdAt`
+    #[param(inline)]
+    sort: Option<SortDirection>,
+    /// Orders per page, between 1 and 100, 20 by default
+    limit: Option<u32>,
+    /// `nextCursor` of the previous page
+    cursor: Option<String>,
+}
+
+impl ListOrdersHttpRequestQuery {
+    fn into_domain(self, username: UserName) -> Result<ListOrdersRequest, ApiError> {
+        let statuses = self.status
+            .iter()
+            .flat_map(|statuses| statuses.split(','))
+            .filter(|status| !status.trim().is_empty())
+            .map(|status| status.trim().parse::<OrderStatus>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ApiError::BadRequest)?;
+        let filter = OrderFilter::new(statuses, self.created_from, self.created_before)?;
+        let sort = match self.sort.unwrap_or_default() {
+            SortDirection::Desc => SortOrder::NewestFirst,
+            SortDirection::Asc => SortOrder::OldestFirst,
+        };
+        let after = self.cursor
+            .as_deref()
+            .map(str::parse::<OrderCursor>)
+            .transpose()?;
+
+        Ok(ListOrdersRequest::new(username, filter, sort, self.limit, after)?)
+    }
+}
 
 #[utoipa::path(
     get,
     path="/api/payment/allordersforuser",
-
+    params(
+       ListOrdersHttpRequestQuery
+    ),
     responses(
-    (status = 200, description = "order", body = Vec<OrderResponseData>)
-    )
+    (status = 200, description = "One page of the user's orders", body = OrderPageResponseData),
+    (status = 400, description = "Unknown status, empty date range, invalid cursor or page size")
+    ),
+    security(("bearer_auth" = []))
 )]
 pub async fn get_all_orders_for_user<OS: OrderService, PS: PaymentService>(
     token: KeycloakToken,
-    state: Data<AppState<OS, PS>>
+    state: Data<AppState<OS, PS>>,
+    query: Query<ListOrdersHttpRequestQuery>,
 ) -> Result<impl Responder, ApiError>{
-    let username_request = token.claims().preferred_username();
-    let username = UserName::new(username_request);
+    let username = UserName::new(token.claims().preferred_username());
+    let domain_req = query.into_inner().into_domain(username)?;
 
     state.order_service
-        .find_orders_by_username(&username)
+        .list_orders(&domain_req)
         .await
         .map_err(ApiError::from)
-        .map(|orders| {
-            let response_data: Vec<OrderResponseData> = orders
-            .iter()
-            .map(OrderResponseData::from)
-            .collect();
-
-            ApiResponseBody::new(StatusCode::OK, response_data)
-        })
+        .and_then(|page| Ok(ApiResponseBody::new(StatusCode::OK, OrderPageResponseData::try_from(&page)?)))
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use uuid::Uuid;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
-use crate::inbound::http::extractors::auth::KeycloakToken;
+use crate::inbound::http::extractors::auth::{OrdersRead, RequireScope};
 use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
 use crate::inbound::http::responses::OrderResponseData;
 
@@ -30,24 +30,27 @@ impl GetByIdHttpRequestQuery {
        GetByIdHttpRequestQuery
     ),
     responses(
-    (status = 200, description = "order", body = OrderResponseData)
-    )
+    (status = 200, description = "order", body = OrderResponseData),
+    (status = 403, description = "Order belongs to another user or the client lacks the orders:read scope"),
+    (status = 404, description = "Order does not exist")
+    ),
+    security(("bearer_auth" = ["orders:read"]))
 )]
 pub async fn get_order_by_id<OS: OrderService, PS: PaymentService>(
     state: Data<AppState<OS, PS>>,
     query: Query<GetByIdHttpRequestQuery>,
-    _: KeycloakToken,
+    principal: RequireScope<OrdersRead>,
 ) -> Result<impl Responder, ApiError> { 
     let domain_req = query.into_inner().into_domain();
     
     state
         .order_service
-        .find_order_by_id(domain_req)
+        .find_order_by_id(domain_req, &principal.caller())
         .await
         .map_err(ApiError::from)
-        .map(|order| {
-            let response = OrderResponseData::from(&order);
-            ApiResponseBody::new(StatusCode::OK, response)
+        .and_then(|order| {
+            let response = OrderResponseData::try_from(&order)?;
+            Ok(ApiResponseBody::new(StatusCode::OK, response))
         })
 }
 
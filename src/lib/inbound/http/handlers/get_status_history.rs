@@ -0,0 +1,45 @@
+use actix_web::http::StatusCode;
+use actix_web::Responder;
+use actix_web::web::{Data, Path};
+use uuid::Uuid;
+use crate::domain::ports::order_service::OrderService;
+use crate::domain::ports::payment_service::PaymentService;
+use crate::inbound::http::AppState;
+use crate::inbound::http::extractors::auth::{OrdersRead, RequireScope};
+use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
+use crate::inbound::http::responses::StatusChangeResponseData;
+
+#[utoipa::path(
+    get,
+    path="/api/payment/order/{id}/history",
+    params(
+        ("id" = Uuid, Path, description = "ID of the order")
+    ),
+    responses(
+    (status = 200, description = "Status changes of the order, oldest first", body = Vec<StatusChangeResponseData>),
+    (status = 403, description = "Order belongs to another user or the client lacks the orders:read scope"),
+    (status = 404, description = "Order never existed")
+    ),
+    security(("bearer_auth" = ["orders:read"]))
+)]
+pub async fn get_status_history<OS: OrderService, PS: PaymentService>(
+    state: Data<AppState<OS, PS>>,
+    path: Path<Uuid>,
+    principal: RequireScope<OrdersRead>,
+) -> Result<impl Responder, ApiError> {
+    let order_id = path.into_inner();
+
+    state
+        .order_service
+        .find_status_history(order_id, &principal.caller())
+        .await
+        .map_err(ApiError::from)
+        .map(|history| {
+            let response_data: Vec<StatusChangeResponseData> = history
+                .iter()
+                .map(StatusChangeResponseData::from)
+                .collect();
+
+            ApiResponseBody::new(StatusCode::OK, response_data)
+        })
+}
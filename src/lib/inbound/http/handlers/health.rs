@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use actix_web::http::StatusCode;
+use actix_web::web::Data;
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::health::{is_ready, DependencyReport, DependencyState, Readiness};
+use crate::inbound::http::handlers::ApiResponseBody;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LivenessResponseData {
+    #[schema(example = "up")]
+    status: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessResponseData {
+    /// `ready` unless a critical dependency is down, `not_ready` otherwise
+    #[schema(example = "ready")]
+    status: &'static str,
+    dependencies: BTreeMap<String, DependencyResponseData>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyResponseData {
+    /// `up` or `down`
+    #[schema(value_type = String, example = "down")]
+    status: DependencyState,
+    /// Whether the service is not ready while the dependency is down
+    critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "channel is closed")]
+    error: Option<String>,
+}
+
+impl From<DependencyReport> for DependencyResponseData {
+    fn from(report: DependencyReport) -> Self {
+        Self {
+            status: *report.state(),
+            critical: *report.critical(),
+            error: report.error().clone(),
+        }
+    }
+}
+
+#[utoipa::path(
+  get,
+  path="/health/live",
+  responses(
+    (status = 200, description = "The server is running", body = LivenessResponseData)
+  )
+)]
+pub async fn live() -> ApiResponseBody<LivenessResponseData> {
+    ApiResponseBody::new(StatusCode::OK, LivenessResponseData { status: "up" })
+}
+
+#[utoipa::path(
+  get,
+  path="/health/ready",
+  responses(
+    (status = 200, description = "All critical dependencies are available", body = ReadinessResponseData),
+    (status = 503, description = "A critical dependency is down", body = ReadinessResponseData)
+  )
+)]
+pub async fn ready(readiness: Data<Readiness>) -> ApiResponseBody<ReadinessResponseData> {
+    let reports = readiness.check().await;
+    let (status_code, status) = if is_ready(&reports) {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
+    ApiResponseBody::new(status_code, ReadinessResponseData {
+        status,
+        dependencies: reports
+            .into_iter()
+            .map(|(dependency, report)| (dependency, report.into()))
+            .collect(),
+    })
+}
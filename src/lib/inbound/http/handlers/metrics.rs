@@ -0,0 +1,41 @@
+use std::time::Instant;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::HttpResponse;
+use crate::inbound::http::handlers::ApiError;
+use crate::metrics::METRICS;
+
+#[utoipa::path(
+  get,
+  path="/metrics",
+  responses(
+    (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain")
+  )
+)]
+pub async fn metrics() -> Result<HttpResponse, ApiError> {
+    let rendered = METRICS
+        .render()
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(rendered))
+}
+
+/// Counts and times every request by the route it matched.
+pub async fn record_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+
+    let res = next.call(req).await?;
+
+    // unmatched paths are whatever clients send, they would flood the labels
+    let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    METRICS.http_request(&method, &route, res.status().as_u16(), started.elapsed());
+
+    Ok(res)
+}
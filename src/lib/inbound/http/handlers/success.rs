@@ -4,7 +4,8 @@ use actix_web::web::{Data, Query};
 use serde::Deserialize;
 use utoipa::IntoParams;
 use crate::domain::models::order::UpdateOrderStatusRequest;
-use crate::domain::models::order_details::SessionId;
+use crate::domain::models::order_details::{OrderStatus, SessionId};
+use crate::domain::models::order_history::Actor;
 use crate::domain::ports::order_service::OrderService;
 use crate::domain::ports::payment_service::PaymentService;
 use crate::inbound::http::AppState;
@@ -29,7 +30,8 @@ impl SuccessHttpRequestQuery {
         SuccessHttpRequestQuery
     ),
     responses(
-    (status = 200, description = "Order", body = OrderResponseData)
+    (status = 200, description = "Order", body = OrderResponseData),
+    (status = 409, description = "Checkout status doesn't fit the current order status")
     )
 )]
 pub async fn success<OS: OrderService, PS: PaymentService>(
@@ -44,13 +46,16 @@ pub async fn success<OS: OrderService, PS: PaymentService>(
         .map_err(ApiError::from)?;
     
     let order_id = *order.details().order_id();
-    let new_status = state
+    let Some(new_status) = state
         .payment_service
         .retrieve_checkout_status(&domain_req)
         .await
-        .map_err(ApiError::from)?;
+        .map_err(ApiError::from)?
+    else {
+        return Ok(ApiResponseBody::new(StatusCode::OK, OrderResponseData::try_from(&order)?));
+    };
     
-    let update_req = UpdateOrderStatusRequest::new(order_id, new_status);
+    let update_req = UpdateOrderStatusRequest::new(order_id, OrderStatus::from(new_status), Actor::StripeCheckout);
     
     let updated_order = state
         .order_service
@@ -58,7 +63,7 @@ pub async fn success<OS: OrderService, PS: PaymentService>(
         .await
         .map_err(ApiError::from)?;
     
-    let response_data = OrderResponseData::from(&updated_order);
+    let response_data = OrderResponseData::try_from(&updated_order)?;
     
     Ok(ApiResponseBody::new(StatusCode::OK, response_data))
     
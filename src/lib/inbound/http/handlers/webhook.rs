@@ -0,0 +1,50 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, Responder};
+use actix_web::web::{Bytes, Data};
+use crate::domain::ports::order_service::OrderService;
+use crate::domain::ports::payment_service::PaymentService;
+use crate::inbound::http::AppState;
+use crate::inbound::http::handlers::{ApiError, ApiResponseBody};
+
+const STRIPE_SIGNATURE_HEADER: &str = "Stripe-Signature";
+
+#[utoipa::path(
+    post,
+    path="/api/payment/webhook",
+    request_body(content = String, description = "Raw Stripe event payload", content_type = "application/json"),
+    params(
+        ("Stripe-Signature" = String, Header, description = "Signature of the payload computed by Stripe")
+    ),
+    responses(
+    (status = 200, description = "ID of the updated order, null if the event was ignored", body = Option<Uuid>),
+    (status = 400, description = "Missing or invalid signature")
+    )
+)]
+pub async fn webhook<OS: OrderService, PS: PaymentService>(
+    state: Data<AppState<OS, PS>>,
+    req: HttpRequest,
+    body: Bytes,
+) -> Result<impl Responder, ApiError> {
+    let signature = req
+        .headers()
+        .get(STRIPE_SIGNATURE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest(format!("Missing {STRIPE_SIGNATURE_HEADER} header")))?;
+    let payload = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::BadRequest("Payload is not valid UTF-8".to_string()))?;
+
+    let Some(event) = state
+        .payment_service
+        .parse_webhook_event(payload, signature)
+        .map_err(ApiError::from)?
+    else {
+        return Ok(ApiResponseBody::new(StatusCode::OK, None));
+    };
+
+    state
+        .order_service
+        .handle_payment_event(&event)
+        .await
+        .map_err(ApiError::from)
+        .map(|order| ApiResponseBody::new(StatusCode::OK, Some(*order.details().order_id())))
+}
@@ -0,0 +1,57 @@
+use std::time::Instant;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use tracing::Instrument;
+use uuid::Uuid;
+use crate::telemetry::{in_trace, TraceContext, TRACEPARENT};
+
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Handles every request in its own span and trace, continuing the caller's trace if
+/// it sent a `traceparent`. The request id is taken from `x-request-id` if the caller
+/// sent one and returned in the same header.
+///
+/// Only method and path are logged, headers and bodies may carry tokens.
+pub async fn trace_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let started = Instant::now();
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map_or_else(|| Uuid::new_v4().to_string(), ToString::to_string);
+    let trace = req
+        .headers()
+        .get(TRACEPARENT)
+        .and_then(|traceparent| traceparent.to_str().ok())
+        .and_then(|traceparent| traceparent.parse::<TraceContext>().ok())
+        .map_or_else(TraceContext::new_root, |parent| parent.child());
+    let span = tracing::info_span!(
+        "http_request",
+        method = %req.method(),
+        path = req.path(),
+        request_id = request_id.as_str(),
+        trace_id = %trace.trace_id(),
+        span_id = %trace.span_id(),
+    );
+
+    let mut res = in_trace(trace, next.call(req))
+        .instrument(span.clone())
+        .await?;
+
+    span.in_scope(|| tracing::info!(
+        status = res.status().as_u16(),
+        elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+        "request finished",
+    ));
+    if let Ok(request_id) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID, request_id);
+    }
+
+    Ok(res)
+}
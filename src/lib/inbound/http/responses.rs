@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::Serialize;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use crate::domain::models::order::Order;
 use crate::domain::models::order_details::OrderDetails;
-use crate::domain::models::order_item::OrderItem;
+use crate::domain::models::order_history::StatusChange;
+use crate::domain::models::order_item::{Currency, OrderItem};
+use crate::domain::models::order_page::OrderPage;
+use crate::inbound::http::handlers::ApiError;
 
 /// Generic response structure shared by all API responses.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -25,22 +29,71 @@ pub struct OrderResponseData {
     id: Uuid,
     items: Vec<OrderItemResponse>,
     #[serde(alias = "metadata")]
-    details: CheckoutDetailsResponse
+    details: CheckoutDetailsResponse,
+    /// Sum of all line totals in the minor unit of the order's currency
+    subtotal: i64,
+    /// VAT included in `total`
+    tax: i64,
+    shipping: i64,
+    /// `subtotal` plus `shipping`, the amount charged
+    total: i64,
 }
 
-impl From<&Order> for OrderResponseData {
-    fn from(order: &Order) -> Self {
+impl TryFrom<&Order> for OrderResponseData {
+    type Error = ApiError;
+
+    fn try_from(order: &Order) -> Result<Self, Self::Error> {
         let id = order.details().order_id().clone();
         let items = order
             .items()
             .iter()
-            .map(OrderItemResponse::from)
-            .collect();
+            .map(OrderItemResponse::try_from)
+            .collect::<Result<_, _>>()?;
         let details = CheckoutDetailsResponse::from(order.details());
+        let currency = *order.details().currency();
+        let totals = order.totals();
+        let minor_units = |amount| minor_units(amount, currency);
 
-        Self {
-            id, items, details
-        }
+        Ok(Self {
+            id,
+            items,
+            details,
+            subtotal: minor_units(*totals.subtotal())?,
+            tax: minor_units(*totals.tax())?,
+            shipping: minor_units(*totals.shipping())?,
+            total: minor_units(*totals.total())?,
+        })
+    }
+}
+
+/// Amounts are stored as decimals but handed out in the currency's minor unit.
+fn minor_units(amount: Decimal, currency: Currency) -> Result<i64, ApiError> {
+    currency.to_minor_units(amount).ok_or_else(|| {
+        tracing::error!(%amount, %currency, "amount doesn't fit into minor units");
+        ApiError::InternalServerError(format!("{amount} {currency} can't be given in minor units"))
+    })
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderPageResponseData {
+    orders: Vec<OrderResponseData>,
+    /// Orders matching the filter on all pages
+    total: u64,
+    /// Pass as `cursor` to get the next page, `null` on the last page
+    #[schema(example = "1738404000000042_0194c1f2a3b47c6d8e9f0a1b2c3d4e5f")]
+    next_cursor: Option<String>,
+}
+
+impl TryFrom<&OrderPage> for OrderPageResponseData {
+    type Error = ApiError;
+
+    fn try_from(page: &OrderPage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            orders: page.orders().iter().map(OrderResponseData::try_from).collect::<Result<_, _>>()?,
+            total: *page.total(),
+            next_cursor: page.next_cursor().map(|cursor| cursor.to_string()),
+        })
     }
 }
 
@@ -48,17 +101,27 @@ impl From<&Order> for OrderResponseData {
 #[serde(rename_all = "camelCase")]
 pub struct OrderItemResponse {
     name: String,
+    /// In the minor unit of the order's currency, e.g. cents
     item_price: i64,
+    quantity: u32,
+    /// `itemPrice` times `quantity`
+    line_total: i64,
     plant_id: Uuid,
 }
 
-impl From<&OrderItem> for OrderItemResponse {
-    fn from(item: &OrderItem) -> Self {
-        Self {
+impl TryFrom<&OrderItem> for OrderItemResponse {
+    type Error = ApiError;
+
+    fn try_from(item: &OrderItem) -> Result<Self, Self::Error> {
+        let currency = *item.price().currency();
+
+        Ok(Self {
             name: item.product_name().to_string(),
-            item_price: item.price().as_cents().unwrap(),
+            item_price: minor_units(*item.price().amount(), currency)?,
+            quantity: item.quantity().value(),
+            line_total: minor_units(*item.total().amount(), currency)?,
             plant_id: item.id().clone(),
-        }
+        })
     }
 }
 
@@ -68,6 +131,8 @@ pub struct CheckoutDetailsResponse {
     username: String,
     status: String,
     session_id: String,
+    /// ISO 4217 code
+    currency: String,
     created_at: DateTime<Utc>
 }
 
@@ -75,10 +140,34 @@ impl From<&OrderDetails> for CheckoutDetailsResponse {
     fn from(details: &OrderDetails) -> Self {
         Self {
             username: details.username().to_string(),
-            status: details.status().clone()
-                .map_or("None".to_string(), |s| s.to_string()),
+            status: details.status().to_string(),
             session_id: details.session_id().to_string(),
+            currency: details.currency().to_string(),
             created_at: details.created_at().clone(),
         }
     }
 }
+
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusChangeResponseData {
+    /// `null` when the order was created
+    previous_status: Option<String>,
+    /// `null` when the order was deleted
+    new_status: Option<String>,
+    /// `user:{username}`, `service:{client id}`, `stripe-webhook` or `stripe-checkout`
+    actor: String,
+    changed_at: DateTime<Utc>,
+}
+
+impl From<&StatusChange> for StatusChangeResponseData {
+    fn from(change: &StatusChange) -> Self {
+        Self {
+            previous_status: change.previous_status().map(|s| s.to_string()),
+            new_status: change.new_status().map(|s| s.to_string()),
+            actor: change.actor().to_string(),
+            changed_at: *change.changed_at(),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use actix_web::http::StatusCode;
+use actix_web::web::Data;
+use actix_web::{test, App};
+use anyhow::anyhow;
+use chrono::Utc;
+use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use stripe::CheckoutSession;
+use uuid::Uuid;
+use crate::domain::models::order::{CreateOrderError, DeleteOrderError, FindOrderError, Order, UpdateOrderError};
+use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, SessionStatus, UserName};
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderPage};
+use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+use crate::domain::models::order_totals::{OrderTotals, PricingPolicy};
+use crate::domain::models::payment_event::PaymentEvent;
+use crate::domain::models::product::Product;
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+use crate::domain::ports::order_repository::OrderRepository;
+use crate::domain::ports::payment_service::{PaymentService, PaymentServiceError};
+use crate::domain::ports::product_catalog::{ProductCatalog, ProductCatalogError};
+use crate::domain::services::order_service::DefaultOrderService;
+use crate::inbound::http::authorization::jwks::{JwksCache, VerificationKey};
+use crate::inbound::http::authorization::oidc::TokenVerifier;
+use crate::health::Readiness;
+use crate::inbound::http::handlers::metrics::record_request;
+use crate::inbound::http::request_tracing::trace_request;
+use crate::inbound::http::{api_routes, AppState, AuthState};
+
+const KID: &str = "test-key";
+const SECRET: &[u8] = b"test-secret";
+
+#[derive(Clone, Default)]
+struct FakeRepository {
+    orders: Arc<Mutex<Vec<Order>>>,
+}
+
+impl FakeRepository {
+    fn find(&self, predicate: impl Fn(&Order) -> bool) -> Option<Order> {
+        self.orders.lock().unwrap().iter().find(|order| predicate(order)).cloned()
+    }
+}
+
+impl OrderRepository for FakeRepository {
+    async fn find_order_by_session_id(&self, req: &SessionId) -> Result<Order, FindOrderError> {
+        self.find(|order| order.details().session_id() == req)
+            .ok_or_else(|| FindOrderError::SessionNotFound { session_id: req.clone() })
+    }
+
+    async fn list_orders(&self, req: &ListOrdersRequest) -> Result<OrderPage, FindOrderError> {
+        let mut orders: Vec<Order> = self.orders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|order| order.details().username() == req.username())
+            .filter(|order| req.filter().statuses().is_empty() || req.filter().statuses().contains(order.details().status()))
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| std::cmp::Reverse(*order.details().created_at()));
+        let total = orders.len() as u64;
+        orders.truncate(*req.limit() as usize);
+
+        Ok(OrderPage::new(orders, total, None))
+    }
+
+    async fn create_order(&self, _req: &Order) -> Result<Uuid, CreateOrderError> {
+        Err(CreateOrderError::Unknown(anyhow!("not used in handler tests")))
+    }
+
+    async fn delete_order(&self, _req: Uuid, _actor: &Actor) -> Result<Uuid, DeleteOrderError> {
+        Err(DeleteOrderError::Unknown(anyhow!("not used in handler tests")))
+    }
+
+    async fn delete_all_orders(&self) -> Result<(), DeleteOrderError> {
+        self.orders.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn find_order_by_id(&self, req: Uuid) -> Result<Order, FindOrderError> {
+        self.find(|order| order.details().order_id() == &req)
+            .ok_or(FindOrderError::IdNotFound { id: req })
+    }
+
+    async fn update_order_status(&self, id: &Uuid, _from: &OrderStatus, to: &OrderStatus, _actor: &Actor) -> Result<Order, UpdateOrderError> {
+        let mut orders = self.orders.lock().unwrap();
+        let order = orders
+            .iter_mut()
+            .find(|order| order.details().order_id() == id)
+            .ok_or(UpdateOrderError::NotFound)?;
+        order.transition_to(*to)?;
+
+        Ok(order.clone())
+    }
+
+    async fn apply_payment_event(&self, _event: &PaymentEvent, _id: &Uuid, _from: &OrderStatus, _to: &OrderStatus) -> Result<Option<Order>, UpdateOrderError> {
+        Err(UpdateOrderError::Unknown(anyhow!("not used in handler tests")))
+    }
+
+    async fn find_status_history(&self, id: &Uuid) -> Result<Vec<StatusChange>, FindOrderError> {
+        let history = self
+            .find(|order| order.details().order_id() == id)
+            .map(|order| StatusChange::new(
+                *id,
+                None,
+                Some(OrderStatus::AwaitingPayment),
+                Actor::User(order.details().username().clone()),
+                *order.details().created_at(),
+            ))
+            .into_iter()
+            .collect();
+
+        Ok(history)
+    }
+}
+
+#[derive(Clone, Default)]
+struct FakePaymentService {
+    expired: Arc<Mutex<Vec<SessionId>>>,
+}
+
+impl PaymentService for FakePaymentService {
+    async fn create_checkout_session(&self, _order_items: &Vec<OrderItem>, _shipping: Option<&Price>) -> Result<CheckoutSession, PaymentServiceError> {
+        Err(PaymentServiceError::Unknown(anyhow!("not used in handler tests")))
+    }
+
+    async fn retrieve_checkout_status(&self, _id: &SessionId) -> Result<Option<SessionStatus>, PaymentServiceError> {
+        Err(PaymentServiceError::Unknown(anyhow!("not used in handler tests")))
+    }
+
+    async fn expire_session(&self, id: &SessionId) -> Result<(), PaymentServiceError> {
+        self.expired.lock().unwrap().push(id.clone());
+        Ok(())
+    }
+
+    fn parse_webhook_event(&self, _payload: &str, _signature: &str) -> Result<Option<PaymentEvent>, PaymentServiceError> {
+        Err(PaymentServiceError::Unknown(anyhow!("not used in handler tests")))
+    }
+}
+
+#[derive(Clone)]
+struct FakeCatalog;
+
+impl ProductCatalog for FakeCatalog {
+    async fn find_products(&self, _ids: &[Uuid], _currency: Currency) -> Result<Vec<Product>, ProductCatalogError> {
+        Err(ProductCatalogError::Unknown(anyhow!("not used in handler tests")))
+    }
+}
+
+type TestOrderService = DefaultOrderService<FakeRepository, FakePaymentService, FakeCatalog>;
+
+fn create_order(owner: &str) -> Order {
+    let details = OrderDetails::new(
+        Uuid::new_v4(),
+        UserName::new(owner),
+        OrderStatus::AwaitingPayment,
+        SessionId::new(&format!("cs_test_{owner}")),
+        Currency::Eur,
+        OrderTotals::new(Decimal::TEN, Decimal::new(160, 2), Decimal::ZERO, Decimal::TEN),
+        Utc::now(),
+    );
+    let item = OrderItem::new(
+        Uuid::new_v4(),
+        ProductName::new("Monstera"),
+        Uuid::new_v4(),
+        Price::from_minor_units(1000, Currency::Eur).unwrap(),
+        Quantity::new(1).unwrap(),
+    );
+
+    Order::new(details, vec![item]).unwrap()
+}
+
+fn bearer(username: &str, roles: &[&str]) -> (&'static str, String) {
+    sign(json!({
+        "exp": Utc::now().timestamp() + 60,
+        "preferred_username": username,
+        "realm_access": { "roles": roles },
+    }))
+}
+
+/// A client credentials token the way Keycloak issues them.
+fn service_bearer(client_id: &str, scope: &str) -> (&'static str, String) {
+    sign(json!({
+        "exp": Utc::now().timestamp() + 60,
+        "preferred_username": format!("service-account-{client_id}"),
+        "azp": client_id,
+        "scope": scope,
+    }))
+}
+
+fn sign(claims: Value) -> (&'static str, String) {
+    let header = Header { kid: Some(KID.to_string()), ..Header::new(Algorithm::HS256) };
+    let token = encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+
+    ("Authorization", format!("Bearer {token}"))
+}
+
+async fn call(
+    repository: &FakeRepository,
+    payment_service: &FakePaymentService,
+    req: test::TestRequest,
+) -> (StatusCode, Value) {
+    let verifier = TokenVerifier::discovered(
+        JwksCache::with_keys(HashMap::from([(
+            KID.to_string(),
+            VerificationKey { key: DecodingKey::from_secret(SECRET), algorithm: Algorithm::HS256 },
+        )])),
+        Validation::new(Algorithm::HS256),
+    );
+
+    call_verified_by(verifier, repository, payment_service, req).await
+}
+
+async fn call_verified_by(
+    verifier: TokenVerifier,
+    repository: &FakeRepository,
+    payment_service: &FakePaymentService,
+    req: test::TestRequest,
+) -> (StatusCode, Value) {
+    let order_service = DefaultOrderService::new(
+        repository.clone(),
+        Arc::new(payment_service.clone()),
+        FakeCatalog,
+        PricingPolicy::standard().unwrap(),
+    );
+    let app_state = Data::new(AppState {
+        order_service: Arc::new(order_service),
+        payment_service: Arc::new(payment_service.clone()),
+        default_currency: Currency::Eur,
+    });
+    let auth_state = Data::new(AuthState { verifier });
+    let app = test::init_service(
+        App::new()
+            .app_data(app_state)
+            .app_data(auth_state)
+            .configure(api_routes::<TestOrderService, FakePaymentService>),
+    ).await;
+
+    let res = test::call_service(&app, req.to_request()).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn setup(owner: &str) -> (FakeRepository, FakePaymentService, Order) {
+    let repository = FakeRepository::default();
+    let order = create_order(owner);
+    repository.orders.lock().unwrap().push(order.clone());
+
+    (repository, FakePaymentService::default(), order)
+}
+
+#[actix_web::test]
+async fn get_order_by_id_returns_own_order() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(bearer("Hannes", &[]));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], json!(order.details().order_id()));
+}
+
+#[actix_web::test]
+async fn get_order_by_id_rejects_other_user() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(bearer("Mallory", &[]));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["status_code"], json!(403));
+}
+
+#[actix_web::test]
+async fn get_order_by_id_allows_admin() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(bearer("Support", &["admin"]));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn get_order_by_id_requires_token() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn cancel_rejects_other_user() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/cancel?session_id={}", order.details().session_id()))
+        .insert_header(bearer("Mallory", &[]));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(payment_service.expired.lock().unwrap().is_empty());
+    assert_eq!(
+        repository.find(|_| true).unwrap().details().status(),
+        &OrderStatus::AwaitingPayment
+    );
+}
+
+#[actix_web::test]
+async fn cancel_expires_own_session() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/cancel?session_id={}", order.details().session_id()))
+        .insert_header(bearer("Hannes", &[]));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!(order.details().order_id()));
+    assert_eq!(*payment_service.expired.lock().unwrap(), vec![order.details().session_id().clone()]);
+    assert_eq!(
+        repository.find(|_| true).unwrap().details().status(),
+        &OrderStatus::Cancelled
+    );
+}
+
+#[actix_web::test]
+async fn list_orders_filters_own_orders_by_status() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let mut paid = create_order("Hannes");
+    paid.transition_to(OrderStatus::Paid).unwrap();
+    repository.orders.lock().unwrap().extend([paid.clone(), create_order("Mallory")]);
+    let req = test::TestRequest::get()
+        .uri("/api/payment/allordersforuser?status=paid,awaiting_payment&limit=1")
+        .insert_header(bearer("Hannes", &[]));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], json!(2));
+    assert_eq!(body["orders"][0]["id"], json!(paid.details().order_id()));
+    assert_ne!(body["orders"][0]["id"], json!(order.details().order_id()));
+    assert_eq!(body["nextCursor"], Value::Null);
+}
+
+#[actix_web::test]
+async fn list_orders_rejects_invalid_query() {
+    let (repository, payment_service, _) = setup("Hannes");
+
+    for query in ["status=shipped", "limit=0", "cursor=yesterday", "created_from=2025-02-01T00:00:00Z&created_before=2025-01-01T00:00:00Z"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/payment/allordersforuser?{query}"))
+            .insert_header(bearer("Hannes", &[]));
+
+        let (status, _) = call(&repository, &payment_service, req).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[actix_web::test]
+async fn status_history_rejects_other_user() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/order/{}/history", order.details().order_id()))
+        .insert_header(bearer("Mallory", &[]));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn delete_all_orders_requires_admin() {
+    let (repository, payment_service, _) = setup("Hannes");
+    let req = test::TestRequest::delete()
+        .uri("/api/payment/orders")
+        .insert_header(bearer("Hannes", &["user"]));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["data"]["message"], json!("Forbidden: requires role admin"));
+    assert!(repository.find(|_| true).is_some());
+}
+
+#[actix_web::test]
+async fn get_order_by_id_allows_service_with_scope() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(service_bearer("fulfilment", "profile orders:read"));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn get_order_by_id_rejects_service_without_scope() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(service_bearer("fulfilment", "profile"));
+
+    let (status, body) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["data"]["message"], json!("Forbidden: requires scope orders:read"));
+}
+
+#[actix_web::test]
+async fn tokens_are_refused_until_keycloak_was_discovered() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(bearer("Hannes", &["user"]));
+
+    let (status, _) = call_verified_by(TokenVerifier::default(), &repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[actix_web::test]
+async fn get_order_by_id_rejects_user_token_without_username() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/orderbyid?order_id={}", order.details().order_id()))
+        .insert_header(sign(json!({
+            "exp": Utc::now().timestamp() + 60,
+            "azp": "frontend",
+            "scope": "profile orders:read",
+        })));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn cancel_requires_user_token() {
+    let (repository, payment_service, order) = setup("Hannes");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/payment/cancel?session_id={}", order.details().session_id()))
+        .insert_header(service_bearer("basket", "orders:read"));
+
+    let (status, _) = call(&repository, &payment_service, req).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(payment_service.expired.lock().unwrap().is_empty());
+}
+
+#[derive(Clone)]
+struct FakeDependency;
+
+impl HealthCheck for FakeDependency {
+    async fn check(&self) -> Result<(), HealthCheckError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+async fn readiness_fails_when_a_critical_dependency_is_down() {
+    let readiness = Readiness::default()
+        .critical("keycloak", TokenVerifier::default())
+        .optional("rabbitmq", FakeDependency);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(readiness))
+            .configure(api_routes::<TestOrderService, FakePaymentService>),
+    ).await;
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/health/ready").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body, json!({
+        "status": "not_ready",
+        "dependencies": {
+            "keycloak": {"status": "down", "critical": true, "error": "OpenID configuration not discovered yet"},
+            "rabbitmq": {"status": "up", "critical": false}
+        }
+    }));
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/health/live").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_by_route() {
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(record_request))
+            .configure(api_routes::<TestOrderService, FakePaymentService>),
+    ).await;
+
+    test::call_service(&app, test::TestRequest::get().uri("/health/live").to_request()).await;
+    let res = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+    assert!(body.contains(r#"http_requests_total{method="GET",route="/health/live",status="200"}"#));
+}
+
+#[actix_web::test]
+async fn request_id_is_returned_to_the_caller() {
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(trace_request))
+            .configure(api_routes::<TestOrderService, FakePaymentService>),
+    ).await;
+
+    let res = test::call_service(&app, test::TestRequest::get()
+        .uri("/health/live")
+        .insert_header(("x-request-id", "checkout-42"))
+        .to_request()).await;
+    let generated = test::call_service(&app, test::TestRequest::get().uri("/health/live").to_request()).await;
+
+    assert_eq!(res.headers().get("x-request-id").unwrap(), "checkout-42");
+    assert!(generated.headers().get("x-request-id").unwrap().to_str().unwrap().parse::<Uuid>().is_ok());
+}
@@ -1,3 +1,7 @@
+pub mod config;
+pub mod health;
+pub mod metrics;
+pub mod telemetry;
 pub mod domain;
 pub mod outbound;
 pub mod inbound;
\ No newline at end of file
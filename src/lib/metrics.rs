@@ -0,0 +1,151 @@
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use prometheus::core::Collector;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use crate::domain::models::order_event::OrderEventKind;
+
+/// Metrics of the whole process, served in the Prometheus text format on `/metrics`.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Upper bounds in seconds, from a cached lookup up to a slow Stripe call.
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    stripe_request_duration: HistogramVec,
+    stripe_errors: IntCounterVec,
+    rabbitmq_publishes: IntCounterVec,
+    order_events: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Handled HTTP requests"),
+            &["method", "route", "status"],
+        ).expect("valid metric");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time spent handling HTTP requests")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["method", "route"],
+        ).expect("valid metric");
+        let stripe_request_duration = HistogramVec::new(
+            HistogramOpts::new("stripe_request_duration_seconds", "Time spent waiting for the Stripe API")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["operation"],
+        ).expect("valid metric");
+        let stripe_errors = IntCounterVec::new(
+            Opts::new("stripe_errors_total", "Failed Stripe API calls"),
+            &["operation"],
+        ).expect("valid metric");
+        let rabbitmq_publishes = IntCounterVec::new(
+            Opts::new("rabbitmq_publishes_total", "Order events published to RabbitMQ, by what the broker answered"),
+            &["outcome"],
+        ).expect("valid metric");
+        let order_events = IntCounterVec::new(
+            Opts::new("order_events_total", "Orders created, paid, expired and cancelled"),
+            &["event"],
+        ).expect("valid metric");
+
+        let metrics = Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            stripe_request_duration,
+            stripe_errors,
+            rabbitmq_publishes,
+            order_events,
+        };
+        for collector in [
+            Box::new(metrics.http_requests.clone()) as Box<dyn Collector>,
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.stripe_request_duration.clone()),
+            Box::new(metrics.stripe_errors.clone()),
+            Box::new(metrics.rabbitmq_publishes.clone()),
+            Box::new(metrics.order_events.clone()),
+        ] {
+            metrics.registry.register(collector).expect("metric names are unique");
+        }
+
+        metrics
+    }
+
+    /// Adds metrics that are collected elsewhere, e.g. read from a connection pool on every scrape.
+    ///
+    /// # Errors
+    /// If a metric with the same name is already registered.
+    pub fn register(&self, collector: impl Collector + 'static) -> Result<(), prometheus::Error> {
+        self.registry.register(Box::new(collector))
+    }
+
+    /// All metrics in the Prometheus text format.
+    ///
+    /// # Errors
+    /// If a metric can't be encoded.
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        TextEncoder::new().encode_to_string(&self.registry.gather())
+    }
+
+    /// `route` is the pattern the request matched, e.g. `/api/payment/order/{id}/history`,
+    /// so that ids don't end up in labels.
+    pub fn http_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Times a call to the Stripe API and counts it if it fails.
+    ///
+    /// # Errors
+    /// The error of `call`.
+    pub async fn stripe_call<T, E>(&self, operation: &str, call: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let started = Instant::now();
+        let result = call.await;
+
+        self.stripe_request_duration
+            .with_label_values(&[operation])
+            .observe(started.elapsed().as_secs_f64());
+        if result.is_err() {
+            self.stripe_errors.with_label_values(&[operation]).inc();
+        }
+
+        result
+    }
+
+    pub fn rabbitmq_publish(&self, outcome: &str) {
+        self.rabbitmq_publishes.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn order_event(&self, kind: OrderEventKind) {
+        self.order_events.with_label_values(&[kind.short_name()]).inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::domain::models::order_event::OrderEventKind;
+    use crate::metrics::Metrics;
+
+    #[tokio::test]
+    async fn renders_recorded_metrics() {
+        let metrics = Metrics::new();
+
+        metrics.http_request("GET", "/api/payment/order/{id}/history", 200, Duration::from_millis(3));
+        metrics.order_event(OrderEventKind::Paid);
+        let _ = metrics.stripe_call("expire_session", async { Err::<(), _>("timeout") }).await;
+        let rendered = metrics.render().unwrap();
+
+        assert!(rendered.contains(r#"http_requests_total{method="GET",route="/api/payment/order/{id}/history",status="200"} 1"#));
+        assert!(rendered.contains(r#"http_request_duration_seconds_bucket{method="GET",route="/api/payment/order/{id}/history",le="0.005"} 1"#));
+        assert!(rendered.contains(r#"order_events_total{event="paid"} 1"#));
+        assert!(rendered.contains(r#"stripe_errors_total{operation="expire_session"} 1"#));
+    }
+}
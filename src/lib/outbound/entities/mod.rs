@@ -1,2 +1,5 @@
 pub mod order_item;
-pub mod order_details;
\ No newline at end of file
+pub mod order_details;
+pub mod order_history;
+pub mod outbox;
+pub mod product;
\ No newline at end of file
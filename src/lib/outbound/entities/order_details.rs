@@ -2,20 +2,28 @@ use derive_more::From;
 use sqlx::FromRow;
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::types::Uuid;
-use crate::domain::models::order_details::{OrderDetails, SessionId, SessionStatus, UserName};
+use sqlx::types::Decimal;
+use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+use crate::domain::models::order_totals::OrderTotals;
+use crate::outbound::entities::order_item::CurrencyEntity;
 
 #[derive(Debug, FromRow)]
 pub struct FetchOrderDetailsEntity {
     pub id: Uuid,
     pub username: String,       // Nullable column
-    pub status: Option<SessionStatusEntity>, // Nullable column
+    pub status: OrderStatusEntity,
     pub session_id: String,    // Nullable column
+    pub currency: CurrencyEntity,
+    pub subtotal: Decimal,
+    pub tax: Decimal,
+    pub shipping: Decimal,
+    pub total: Decimal,
     pub created_at: DateTime<Utc>,     // Maps to TIMESTAMP
 }
 
 impl FetchOrderDetailsEntity {
     pub fn into_domain(self) -> OrderDetails {
-        let status = self.status.map(SessionStatusEntity::into_domain);
+        let status = self.status.into_domain();
         let username = UserName::new(&self.username);
         let session_id = SessionId::new(&self.session_id);
         
@@ -24,58 +32,93 @@ impl FetchOrderDetailsEntity {
             username,
             status,
             session_id,
+            self.currency.into_domain(),
+            OrderTotals::new(self.subtotal, self.tax, self.shipping, self.total),
             self.created_at,
         )
     }
 }
 
-#[derive(Debug, sqlx::Type)]
-#[sqlx(type_name = "session_status", rename_all = "lowercase")]
-pub enum SessionStatusEntity {
-    Open,
-    Complete,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "order_status", rename_all = "snake_case")]
+pub enum OrderStatusEntity {
+    Pending,
+    AwaitingPayment,
+    Paid,
+    Fulfilled,
+    Cancelled,
     Expired,
+    Refunded,
 }
 #[derive(Debug)]
 pub struct CreateOrderDetailsEntity {
     pub id: Uuid,
     pub username: String,
-    pub status: Option<SessionStatusEntity>,
+    pub status: OrderStatusEntity,
     pub session_id: String,
+    pub currency: CurrencyEntity,
+    pub subtotal: Decimal,
+    pub tax: Decimal,
+    pub shipping: Decimal,
+    pub total: Decimal,
     pub created_at: DateTime<Utc>,
 }
 
-impl From<SessionStatus> for SessionStatusEntity {
-    fn from(value: SessionStatus) -> Self {
+impl From<OrderStatus> for OrderStatusEntity {
+    fn from(value: OrderStatus) -> Self {
         match value {
-            SessionStatus::Open => SessionStatusEntity::Open,
-            SessionStatus::Complete => SessionStatusEntity::Complete,
-            SessionStatus::Expired => SessionStatusEntity::Expired,
+            OrderStatus::Pending => Self::Pending,
+            OrderStatus::AwaitingPayment => Self::AwaitingPayment,
+            OrderStatus::Paid => Self::Paid,
+            OrderStatus::Fulfilled => Self::Fulfilled,
+            OrderStatus::Cancelled => Self::Cancelled,
+            OrderStatus::Expired => Self::Expired,
+            OrderStatus::Refunded => Self::Refunded,
         }
     }
 }
 
-impl SessionStatusEntity {
-    fn into_domain(self) -> SessionStatus {
+impl OrderStatusEntity {
+    /// The value of the Postgres enum.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
         match self {
-            SessionStatusEntity::Open => SessionStatus::Open,
-            SessionStatusEntity::Complete => SessionStatus::Complete,
-            SessionStatusEntity::Expired => SessionStatus::Expired,
+            Self::Pending => "pending",
+            Self::AwaitingPayment => "awaiting_payment",
+            Self::Paid => "paid",
+            Self::Fulfilled => "fulfilled",
+            Self::Cancelled => "cancelled",
+            Self::Expired => "expired",
+            Self::Refunded => "refunded",
+        }
+    }
+
+    #[must_use]
+    pub const fn into_domain(self) -> OrderStatus {
+        match self {
+            Self::Pending => OrderStatus::Pending,
+            Self::AwaitingPayment => OrderStatus::AwaitingPayment,
+            Self::Paid => OrderStatus::Paid,
+            Self::Fulfilled => OrderStatus::Fulfilled,
+            Self::Cancelled => OrderStatus::Cancelled,
+            Self::Expired => OrderStatus::Expired,
+            Self::Refunded => OrderStatus::Refunded,
         }
     }
 }
 
 impl CreateOrderDetailsEntity {
     pub fn from_domain(value: &OrderDetails) -> Self {
-        let status = match value.status().clone() {
-            Some(status) => Some(status.into()),
-            None => None,
-        };
         Self {
             id: value.order_id().clone(),
             username: value.username().to_string(),
-            status,
+            status: OrderStatusEntity::from(*value.status()),
             session_id: value.session_id().to_string(),
+            currency: CurrencyEntity::from(*value.currency()),
+            subtotal: *value.totals().subtotal(),
+            tax: *value.totals().tax(),
+            shipping: *value.totals().shipping(),
+            total: *value.totals().total(),
             created_at: value.created_at().clone(),
         }
     }
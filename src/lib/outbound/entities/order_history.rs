@@ -0,0 +1,36 @@
+use anyhow::anyhow;
+use sqlx::FromRow;
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::types::Uuid;
+use crate::domain::models::order::FindOrderError;
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::outbound::entities::order_details::OrderStatusEntity;
+
+#[derive(Debug, FromRow)]
+pub struct FetchStatusChangeEntity {
+    pub order_id: Uuid,
+    pub previous_status: Option<OrderStatusEntity>,
+    pub new_status: Option<OrderStatusEntity>,
+    pub actor: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl FetchStatusChangeEntity {
+    /// # Errors
+    /// If the stored actor can't be parsed.
+    pub fn try_into_domain(self) -> Result<StatusChange, FindOrderError> {
+        let actor = self.actor
+            .parse::<Actor>()
+            .map_err(|e| FindOrderError::Unknown(anyhow!(e).context(format!(
+                "status change of order {} has an invalid actor", self.order_id
+            ))))?;
+
+        Ok(StatusChange::new(
+            self.order_id,
+            self.previous_status.map(OrderStatusEntity::into_domain),
+            self.new_status.map(OrderStatusEntity::into_domain),
+            actor,
+            self.changed_at,
+        ))
+    }
+}
@@ -1,10 +1,44 @@
 use anyhow::anyhow;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
 use sqlx::FromRow;
 use sqlx::types::{uuid::Uuid};
 use crate::domain::models::order::FindOrderError;
-use crate::domain::models::order_item::{OrderItem, Price, ProductName};
+use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "currency", rename_all = "UPPERCASE")]
+pub enum CurrencyEntity {
+    Eur,
+    Usd,
+    Gbp,
+    Chf,
+    Jpy,
+}
+
+impl From<Currency> for CurrencyEntity {
+    fn from(value: Currency) -> Self {
+        match value {
+            Currency::Eur => Self::Eur,
+            Currency::Usd => Self::Usd,
+            Currency::Gbp => Self::Gbp,
+            Currency::Chf => Self::Chf,
+            Currency::Jpy => Self::Jpy,
+        }
+    }
+}
+
+impl CurrencyEntity {
+    #[must_use]
+    pub const fn into_domain(self) -> Currency {
+        match self {
+            Self::Eur => Currency::Eur,
+            Self::Usd => Currency::Usd,
+            Self::Gbp => Currency::Gbp,
+            Self::Chf => Currency::Chf,
+            Self::Jpy => Currency::Jpy,
+        }
+    }
+}
 
 #[derive(Debug, Clone, FromRow)]
 pub struct CreateOrderItemEntity {
@@ -12,6 +46,8 @@ pub struct CreateOrderItemEntity {
     pub product_name: String,
     pub item_id: Uuid,
     pub price: Decimal,
+    pub currency: CurrencyEntity,
+    pub quantity: i32,
     pub order_id: Uuid,
 }
 
@@ -21,7 +57,9 @@ impl CreateOrderItemEntity {
             id: item.id().clone(),
             product_name: item.product_name().to_string(),
             item_id: item.item_id().clone(),
-            price: item.price().as_ref().clone(),
+            price: *item.price().amount(),
+            currency: CurrencyEntity::from(*item.price().currency()),
+            quantity: i32::try_from(item.quantity().value()).unwrap_or(i32::MAX),
             order_id: order_id.clone(),
         }
     }
@@ -32,6 +70,8 @@ pub struct FetchOrderItemEntity {
     pub id: Uuid,
     pub product_name: String,
     pub price: Decimal,
+    pub currency: CurrencyEntity,
+    pub quantity: i32,
     pub item_id: Uuid,
     pub order_id: Uuid,
 }
@@ -39,14 +79,18 @@ pub struct FetchOrderItemEntity {
 impl FetchOrderItemEntity {
     pub fn try_into_domain(self) -> Result<OrderItem, FindOrderError> {
         let product_name = ProductName::new(&self.product_name);
-        let price = Price::new(self.price.to_f64().unwrap())
+        let price = Price::from_decimal(self.price, self.currency.into_domain())
             .map_err(|e| FindOrderError::Unknown(anyhow!(e)))?;
+        let quantity = u32::try_from(self.quantity)
+            .map_err(|e| FindOrderError::Unknown(anyhow!(e)))
+            .and_then(|raw| Quantity::new(raw).map_err(|e| FindOrderError::Unknown(anyhow!(e))))?;
         
         Ok(OrderItem::new(
             self.id,
             product_name,
             self.item_id,
             price,
+            quantity,
         ))
     }
 }
\ No newline at end of file
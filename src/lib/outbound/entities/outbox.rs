@@ -0,0 +1,31 @@
+use anyhow::{anyhow, Context};
+use sqlx::FromRow;
+use sqlx::types::Uuid;
+use crate::domain::models::outbox::OutboxMessage;
+use crate::domain::ports::outbox_repository::OutboxError;
+use crate::outbound::order_events::OrderEventMessage;
+
+#[derive(Debug, FromRow)]
+pub struct FetchOutboxMessageEntity {
+    pub id: Uuid,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub traceparent: Option<String>,
+}
+
+impl FetchOutboxMessageEntity {
+    /// # Errors
+    /// If the attempt count is negative or the payload isn't a valid order event.
+    pub fn try_into_domain(self) -> Result<OutboxMessage, OutboxError> {
+        let attempts = u32::try_from(self.attempts)
+            .map_err(|e| OutboxError::Unknown(anyhow!(e).context(format!(
+                "outbox message {} has a negative attempt count", self.id
+            ))))?;
+        let event = serde_json::from_value::<OrderEventMessage>(self.payload)
+            .map_err(anyhow::Error::from)
+            .and_then(OrderEventMessage::try_into_domain)
+            .with_context(|| format!("outbox message {} carries an invalid order event", self.id))?;
+
+        Ok(OutboxMessage::new(event, attempts, self.traceparent))
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use sqlx::FromRow;
+use sqlx::types::Uuid;
+use crate::domain::models::order_item::{Price, ProductName};
+use crate::domain::models::product::Product;
+use crate::domain::ports::product_catalog::ProductCatalogError;
+use crate::outbound::entities::order_item::CurrencyEntity;
+
+#[derive(Debug, FromRow)]
+pub struct FetchProductEntity {
+    pub id: Uuid,
+    pub name: String,
+    pub active: bool,
+    pub price: Decimal,
+    pub currency: CurrencyEntity,
+}
+
+impl FetchProductEntity {
+    /// # Errors
+    /// If the stored price isn't a valid price.
+    pub fn try_into_domain(self) -> Result<Product, ProductCatalogError> {
+        let price = Price::from_decimal(self.price, self.currency.into_domain())
+            .map_err(|e| ProductCatalogError::Unknown(anyhow!(e).context(format!(
+                "product {} has an invalid price", self.id
+            ))))?;
+
+        Ok(Product::new(
+            self.id,
+            ProductName::new(&self.name),
+            price,
+            self.active,
+        ))
+    }
+}
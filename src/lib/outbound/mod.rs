@@ -1,3 +1,5 @@
 pub mod entities;
+pub mod order_events;
 pub mod postgres;
+pub mod product_service;
 pub mod rabbitmq;
\ No newline at end of file
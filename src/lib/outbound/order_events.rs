@@ -0,0 +1,329 @@
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::domain::models::order::Order;
+use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+use crate::domain::models::order_event::{OrderEvent, OrderEventKind};
+use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+use crate::domain::models::order_totals::OrderTotals;
+
+/// Version of the published event schema, raised on every breaking change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// An order event as stored in the outbox and published to the message broker.
+/// Amounts are integers in the currency's minor unit, e.g. cents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(title = "OrderEvent")]
+pub struct OrderEventMessage {
+    /// Unique per event, redeliveries keep it
+    pub event_id: Uuid,
+    pub event_type: OrderEventType,
+    /// Version of this schema
+    pub version: u32,
+    pub occurred_at: DateTime<Utc>,
+    pub order_id: Uuid,
+    /// Id of the Stripe checkout session
+    pub session_id: String,
+    pub username: String,
+    /// Status of the order after the event, before the deletion for `OrderDeleted`
+    pub status: OrderStatusMessage,
+    /// ISO 4217 code, e.g. `EUR`
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+    pub items: Vec<OrderEventItem>,
+    pub totals: OrderEventTotals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum OrderEventType {
+    OrderCreated,
+    OrderPaid,
+    OrderExpired,
+    OrderCancelled,
+    OrderDeleted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatusMessage {
+    Pending,
+    AwaitingPayment,
+    Paid,
+    Fulfilled,
+    Cancelled,
+    Expired,
+    Refunded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderEventItem {
+    pub id: Uuid,
+    /// Id of the product
+    pub item_id: Uuid,
+    pub product_name: String,
+    pub quantity: u32,
+    pub unit_price: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderEventTotals {
+    pub subtotal: i64,
+    /// VAT included in `total`
+    pub tax: i64,
+    pub shipping: i64,
+    pub total: i64,
+}
+
+/// The JSON Schema of [`OrderEventMessage`], checked in as `schemas/order-event.v1.json`.
+///
+/// # Panics
+/// Never, the generated schema always serializes.
+#[must_use]
+pub fn json_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(OrderEventMessage)).expect("schema is valid JSON")
+}
+
+impl OrderEventMessage {
+    /// # Errors
+    /// If an amount of the order doesn't fit into minor units.
+    pub fn from_domain(event: &OrderEvent) -> Result<Self, anyhow::Error> {
+        let details = event.order().details();
+        let currency = *details.currency();
+        let minor_units = |amount: Decimal| currency
+            .to_minor_units(amount)
+            .ok_or_else(|| anyhow!("amount {amount} of order {} is out of range", details.order_id()));
+
+        let items = event.order()
+            .items()
+            .iter()
+            .map(|item| Ok(OrderEventItem {
+                id: *item.id(),
+                item_id: *item.item_id(),
+                product_name: item.product_name().to_string(),
+                quantity: item.quantity().value(),
+                unit_price: minor_units(*item.price().amount())?,
+                total: minor_units(*item.total().amount())?,
+            }))
+            .collect::<Result<_, anyhow::Error>>()?;
+        let totals = details.totals();
+
+        Ok(Self {
+            event_id: *event.id(),
+            event_type: OrderEventType::from(*event.kind()),
+            version: SCHEMA_VERSION,
+            occurred_at: *event.occurred_at(),
+            order_id: *details.order_id(),
+            session_id: details.session_id().to_string(),
+            username: details.username().to_string(),
+            status: OrderStatusMessage::from(*details.status()),
+            currency: currency.to_string(),
+            created_at: *details.created_at(),
+            items,
+            totals: OrderEventTotals {
+                subtotal: minor_units(*totals.subtotal())?,
+                tax: minor_units(*totals.tax())?,
+                shipping: minor_units(*totals.shipping())?,
+                total: minor_units(*totals.total())?,
+            },
+        })
+    }
+
+    /// # Errors
+    /// If the message has another schema version or contains invalid values.
+    pub fn try_into_domain(self) -> Result<OrderEvent, anyhow::Error> {
+        if self.version != SCHEMA_VERSION {
+            return Err(anyhow!("unsupported order event version {}", self.version));
+        }
+
+        let currency: Currency = self.currency.parse()?;
+        let amount = |minor_units: i64| Decimal::new(minor_units, currency.minor_unit_exponent());
+        let items = self.items
+            .into_iter()
+            .map(|item| Ok(OrderItem::new(
+                item.id,
+                ProductName::new(&item.product_name),
+                item.item_id,
+                Price::from_minor_units(item.unit_price, currency)?,
+                Quantity::new(item.quantity)?,
+            )))
+            .collect::<Result<_, anyhow::Error>>()?;
+        let totals = OrderTotals::new(
+            amount(self.totals.subtotal),
+            amount(self.totals.tax),
+            amount(self.totals.shipping),
+            amount(self.totals.total),
+        );
+        let details = OrderDetails::new(
+            self.order_id,
+            UserName::new(&self.username),
+            self.status.into(),
+            SessionId::new(&self.session_id),
+            currency,
+            totals,
+            self.created_at,
+        );
+        let order = Order::new(details, items)
+            .with_context(|| format!("order event {} carries an invalid order", self.event_id))?;
+
+        Ok(OrderEvent::new(self.event_id, self.event_type.into(), order, self.occurred_at))
+    }
+}
+
+impl From<OrderEventKind> for OrderEventType {
+    fn from(kind: OrderEventKind) -> Self {
+        match kind {
+            OrderEventKind::Created => Self::OrderCreated,
+            OrderEventKind::Paid => Self::OrderPaid,
+            OrderEventKind::Expired => Self::OrderExpired,
+            OrderEventKind::Cancelled => Self::OrderCancelled,
+            OrderEventKind::Deleted => Self::OrderDeleted,
+        }
+    }
+}
+
+impl From<OrderEventType> for OrderEventKind {
+    fn from(event_type: OrderEventType) -> Self {
+        match event_type {
+            OrderEventType::OrderCreated => Self::Created,
+            OrderEventType::OrderPaid => Self::Paid,
+            OrderEventType::OrderExpired => Self::Expired,
+            OrderEventType::OrderCancelled => Self::Cancelled,
+            OrderEventType::OrderDeleted => Self::Deleted,
+        }
+    }
+}
+
+impl From<OrderStatus> for OrderStatusMessage {
+    fn from(status: OrderStatus) -> Self {
+        match status {
+            OrderStatus::Pending => Self::Pending,
+            OrderStatus::AwaitingPayment => Self::AwaitingPayment,
+            OrderStatus::Paid => Self::Paid,
+            OrderStatus::Fulfilled => Self::Fulfilled,
+            OrderStatus::Cancelled => Self::Cancelled,
+            OrderStatus::Expired => Self::Expired,
+            OrderStatus::Refunded => Self::Refunded,
+        }
+    }
+}
+
+impl From<OrderStatusMessage> for OrderStatus {
+    fn from(status: OrderStatusMessage) -> Self {
+        match status {
+            OrderStatusMessage::Pending => Self::Pending,
+            OrderStatusMessage::AwaitingPayment => Self::AwaitingPayment,
+            OrderStatusMessage::Paid => Self::Paid,
+            OrderStatusMessage::Fulfilled => Self::Fulfilled,
+            OrderStatusMessage::Cancelled => Self::Cancelled,
+            OrderStatusMessage::Expired => Self::Expired,
+            OrderStatusMessage::Refunded => Self::Refunded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use serde_json::json;
+    use uuid::Uuid;
+    use crate::domain::models::order::Order;
+    use crate::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+    use crate::domain::models::order_event::{OrderEvent, OrderEventKind};
+    use crate::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+    use crate::domain::models::order_totals::OrderTotals;
+    use crate::outbound::order_events::{json_schema, OrderEventMessage};
+
+    const SCHEMA_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/order-event.v1.json");
+
+    fn create_event() -> OrderEvent {
+        let item = OrderItem::new(
+            Uuid::from_u128(1),
+            ProductName::new("Monstera"),
+            Uuid::from_u128(2),
+            Price::from_minor_units(1999, Currency::Eur).unwrap(),
+            Quantity::new(2).unwrap(),
+        );
+        let totals = OrderTotals::new(Decimal::new(3998, 2), Decimal::new(718, 2), Decimal::new(499, 2), Decimal::new(4497, 2));
+        let created_at = Utc.with_ymd_and_hms(2025, 2, 1, 10, 0, 0).unwrap();
+        let details = OrderDetails::new(
+            Uuid::from_u128(3),
+            UserName::new("Hannes"),
+            OrderStatus::Paid,
+            SessionId::new("cs_test_123"),
+            Currency::Eur,
+            totals,
+            created_at,
+        );
+
+        OrderEvent::new(
+            Uuid::from_u128(4),
+            OrderEventKind::Paid,
+            Order::new(details, vec![item]).unwrap(),
+            created_at + chrono::Duration::minutes(5),
+        )
+    }
+
+    #[test]
+    fn serializes_version_one() {
+        let message = OrderEventMessage::from_domain(&create_event()).unwrap();
+
+        assert_eq!(serde_json::to_value(&message).unwrap(), json!({
+            "eventId": "00000000-0000-0000-0000-000000000004",
+            "eventType": "OrderPaid",
+            "version": 1,
+            "occurredAt": "2025-02-01T10:05:00Z",
+            "orderId": "00000000-0000-0000-0000-000000000003",
+            "sessionId": "cs_test_123",
+            "username": "Hannes",
+            "status": "paid",
+            "currency": "EUR",
+            "createdAt": "2025-02-01T10:00:00Z",
+            "items": [{
+                "id": "00000000-0000-0000-0000-000000000001",
+                "itemId": "00000000-0000-0000-0000-000000000002",
+                "productName": "Monstera",
+                "quantity": 2,
+                "unitPrice": 1999,
+                "total": 3998
+            }],
+            "totals": {"subtotal": 3998, "tax": 718, "shipping": 499, "total": 4497}
+        }));
+    }
+
+    #[test]
+    fn round_trips_through_the_outbox() {
+        let event = create_event();
+
+        let message = OrderEventMessage::from_domain(&event).unwrap();
+
+        assert_eq!(message.try_into_domain().unwrap(), event);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut message = OrderEventMessage::from_domain(&create_event()).unwrap();
+        message.version = 2;
+
+        assert!(message.try_into_domain().is_err());
+    }
+
+    /// Run with `UPDATE_SCHEMAS=1` to rewrite the checked in schema after changing the event.
+    #[test]
+    fn checked_in_schema_is_up_to_date() {
+        let schema = serde_json::to_string_pretty(&json_schema()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_SCHEMAS").is_some() {
+            std::fs::write(SCHEMA_FILE, &schema).unwrap();
+        }
+
+        let checked_in = std::fs::read_to_string(SCHEMA_FILE).unwrap_or_default();
+        assert_eq!(checked_in, schema, "schemas/order-event.v1.json is outdated");
+    }
+}
@@ -1,25 +1,89 @@
 use crate::domain::models::order::{CreateOrderError, DeleteOrderError, FindOrderError, Order, UpdateOrderError};
-use crate::domain::models::order_details::{SessionId, SessionStatus, UserName};
+use crate::domain::models::order_details::{OrderStatus, SessionId};
+use crate::domain::models::order_history::{Actor, StatusChange};
+use crate::domain::models::order_page::{ListOrdersRequest, OrderCursor, OrderFilter, OrderPage, SortOrder};
+use crate::domain::models::order_item::Currency;
+use crate::domain::models::product::Product;
 use crate::domain::models::order_item::OrderItem;
+use crate::domain::models::order_event::{OrderEvent, OrderEventKind};
+use crate::domain::models::outbox::OutboxMessage;
+use crate::domain::models::payment_event::PaymentEvent;
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
 use crate::domain::ports::order_repository::OrderRepository;
+use crate::domain::ports::outbox_repository::{OutboxError, OutboxRepository};
+use crate::domain::ports::product_catalog::{ProductCatalog, ProductCatalogError};
 use crate::outbound::entities::order_details::FetchOrderDetailsEntity;
-use crate::outbound::entities::order_details::{CreateOrderDetailsEntity, SessionStatusEntity};
-use crate::outbound::entities::order_item::{CreateOrderItemEntity, FetchOrderItemEntity};
+use crate::outbound::entities::order_details::{CreateOrderDetailsEntity, OrderStatusEntity};
+use crate::outbound::entities::order_history::FetchStatusChangeEntity;
+use crate::outbound::entities::order_item::{CreateOrderItemEntity, CurrencyEntity, FetchOrderItemEntity};
+use crate::outbound::entities::outbox::FetchOutboxMessageEntity;
+use crate::outbound::entities::product::FetchProductEntity;
+use crate::outbound::order_events::OrderEventMessage;
+use crate::telemetry::{redact_url, TraceContext};
 use anyhow::{anyhow, Context};
 use rust_decimal::Decimal;
-use sqlx::postgres::PgConnectOptions;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::{Executor, PgPool, Transaction};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
+use std::time::Duration;
 use getset::Getters;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{IntGauge, IntGaugeVec, Opts};
 use uuid::Uuid;
 
+/// Where the database is and how many connections the pool keeps open to it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PostgresConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before it fails.
+    pub acquire_timeout: Duration,
+}
+
+impl Debug for PostgresConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresConfig")
+            .field("url", &redact_url(&self.url))
+            .field("max_connections", &self.max_connections)
+            .field("min_connections", &self.min_connections)
+            .field("acquire_timeout", &self.acquire_timeout)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Getters)]
 #[getset(get = "pub")]
 pub struct Postgres {
     pool: PgPool,
 }
 
+/// Gauges that are read from the pool whenever metrics are scraped.
+pub struct PoolMetrics {
+    pool: PgPool,
+    connections: IntGaugeVec,
+    max_connections: IntGauge,
+}
+
+impl Collector for PoolMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        self.connections.desc().into_iter().chain(self.max_connections.desc()).collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let idle = i64::try_from(self.pool.num_idle()).unwrap_or(i64::MAX);
+        self.connections.with_label_values(&["idle"]).set(idle);
+        self.connections.with_label_values(&["in_use"]).set(i64::from(self.pool.size()) - idle);
+        self.max_connections.set(i64::from(self.pool.options().get_max_connections()));
+
+        self.connections.collect().into_iter().chain(self.max_connections.collect()).collect()
+    }
+}
+
 impl Postgres {
     pub async fn new(path: &str) -> Result<Self, anyhow::Error> {
         let pool = PgPool::connect_with(
@@ -32,18 +96,76 @@ impl Postgres {
         Ok(Self { pool })
     }
 
-    async fn delete_order_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    /// Doesn't connect yet, so that the server can start while the database is down.
+    /// Connections are opened on first use, see [`ping`](Self::ping).
+    ///
+    /// # Errors
+    /// If the database url can't be parsed.
+    pub fn connect_lazy(config: &PostgresConfig) -> Result<Self, anyhow::Error> {
+        let options = PgConnectOptions::from_str(&config.url)
+            .context("invalid database url")?;
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .connect_lazy_with(options);
+
+        Ok(Self { pool })
+    }
+
+    /// Utilisation of the connection pool, to be registered with [`METRICS`](crate::metrics::METRICS).
+    ///
+    /// # Panics
+    /// Never, the metric names and labels are valid.
+    #[must_use]
+    pub fn pool_metrics(&self) -> PoolMetrics {
+        PoolMetrics {
+            pool: self.pool.clone(),
+            connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Open database connections, idle or in use"),
+                &["state"],
+            ).expect("valid metric"),
+            max_connections: IntGauge::new("db_pool_max_connections", "Connections the pool may open")
+                .expect("valid metric"),
+        }
+    }
+
+    /// Checks that the database can be reached.
+    ///
+    /// # Errors
+    /// If no connection can be opened or the query fails.
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        self.pool.execute("SELECT 1").await.map(|_| ())
+    }
+
+    /// Returns the details the order had, `None` if it didn't exist.
+    async fn delete_order_by_id(
+        &self,
+        id: Uuid,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<FetchOrderDetailsEntity>, sqlx::Error> {
+        let details = sqlx::query_as!(
+            FetchOrderDetailsEntity,
             r#"
             DELETE FROM order_details
             WHERE id = $1
+            RETURNING id,
+                      username,
+                      order_status AS "status: OrderStatusEntity",
+                      session_id,
+                      currency AS "currency: CurrencyEntity",
+                      subtotal AS "subtotal: Decimal",
+                      tax AS "tax: Decimal",
+                      shipping AS "shipping: Decimal",
+                      total AS "total: Decimal",
+                      created_at AS "created_at: DateTime<Utc>"
             "#,
         id
         )
-            .execute(&self.pool)
+            .fetch_optional(&mut **tx)
             .await?;
         
-        Ok(())
+        Ok(details)
     }
 
     async fn find_details_by_session_id(
@@ -55,8 +177,13 @@ impl Postgres {
             r#"
             SELECT id,
                    username,
-                   status AS "status: SessionStatusEntity",
+                   order_status AS "status: OrderStatusEntity",
                    session_id,
+                   currency AS "currency: CurrencyEntity",
+                   subtotal AS "subtotal: Decimal",
+                   tax AS "tax: Decimal",
+                   shipping AS "shipping: Decimal",
+                   total AS "total: Decimal",
                    created_at AS "created_at: DateTime<Utc>"
             FROM order_details
             WHERE session_id = $1
@@ -69,31 +196,116 @@ impl Postgres {
         Ok(details)
 
     }
-    async fn find_order_details_by_username(&self,
-                                                username: &UserName
+    /// Fetches one order more than requested, to tell whether there is a next page.
+    async fn find_order_details_page(
+        &self,
+        req: &ListOrdersRequest,
     ) -> Result<Vec<FetchOrderDetailsEntity>, sqlx::Error> {
+        let filter = req.filter();
+        let statuses = status_labels(filter);
+        let oldest_first = *req.sort() == SortOrder::OldestFirst;
+
+        // with oldest_first the CASE keys sort, otherwise they are all NULL and the
+        // descending keys do
         let details: Vec<FetchOrderDetailsEntity> = sqlx::query_as!(
             FetchOrderDetailsEntity,
             r#"
             SELECT id,
                    username,
-                   status AS "status: SessionStatusEntity",
+                   order_status AS "status: OrderStatusEntity",
                    session_id,
+                   currency AS "currency: CurrencyEntity",
+                   subtotal AS "subtotal: Decimal",
+                   tax AS "tax: Decimal",
+                   shipping AS "shipping: Decimal",
+                   total AS "total: Decimal",
                    created_at AS "created_at: DateTime<Utc>"
             FROM order_details
             WHERE username = $1
+              AND (cardinality($2::text[]) = 0 OR order_status = ANY($2::text[]::order_status[]))
+              AND ($3::timestamptz IS NULL OR created_at >= $3)
+              AND ($4::timestamptz IS NULL OR created_at < $4)
+              AND ($5::timestamptz IS NULL
+                   OR ($7 AND (created_at, id) > ($5, $6::uuid))
+                   OR (NOT $7 AND (created_at, id) < ($5, $6::uuid)))
+            ORDER BY CASE WHEN $7 THEN created_at END,
+                     CASE WHEN $7 THEN id END,
+                     created_at DESC,
+                     id DESC
+            LIMIT $8
             "#,
-            username.to_string()
+            req.username().to_string(),
+            &statuses,
+            *filter.created_from(),
+            *filter.created_before(),
+            req.after().map(|cursor| *cursor.created_at()),
+            req.after().map(|cursor| *cursor.order_id()),
+            oldest_first,
+            i64::from(*req.limit()) + 1,
         )
             .fetch_all(&self.pool)
             .await?;
 
         Ok(details)
     }
-    async fn find_order_items_by_order_id(
+
+    async fn count_orders(&self, req: &ListOrdersRequest) -> Result<i64, sqlx::Error> {
+        let filter = req.filter();
+        let statuses = status_labels(filter);
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "total!"
+            FROM order_details
+            WHERE username = $1
+              AND (cardinality($2::text[]) = 0 OR order_status = ANY($2::text[]::order_status[]))
+              AND ($3::timestamptz IS NULL OR created_at >= $3)
+              AND ($4::timestamptz IS NULL OR created_at < $4)
+            "#,
+            req.username().to_string(),
+            &statuses,
+            *filter.created_from(),
+            *filter.created_before(),
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(total)
+    }
+
+    async fn find_order_items_by_order_ids(
         &self,
-        order_id: &Uuid,
+        order_ids: &[Uuid],
     ) -> Result<Vec<FetchOrderItemEntity>, sqlx::Error> {
+        let items: Vec<FetchOrderItemEntity> = sqlx::query_as!(
+            FetchOrderItemEntity,
+            r#"
+            SELECT id,
+                   product_name,
+                   item_id,
+                   price AS "price: Decimal",
+                   currency AS "currency: CurrencyEntity",
+                   quantity,
+                   order_id
+            FROM order_item
+            WHERE order_id = ANY($1)
+            "#,
+            order_ids,
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(items)
+    }
+
+    async fn find_order_items_by_order_id<'e, E>(
+        &self,
+        order_id: &Uuid,
+        executor: E,
+    ) -> Result<Vec<FetchOrderItemEntity>, sqlx::Error>
+    where
+        E: Executor<'e, Database = sqlx::Postgres>,
+    {
 
         let items: Vec<FetchOrderItemEntity> = sqlx::query_as!(
             FetchOrderItemEntity,
@@ -102,13 +314,15 @@ impl Postgres {
                    product_name,
                    item_id,
                    price AS "price: Decimal",
+                   currency AS "currency: CurrencyEntity",
+                   quantity,
                    order_id
             FROM order_item
             WHERE order_id = $1
             "#,
             order_id,
         )
-            .fetch_all(&self.pool)
+            .fetch_all(executor)
             .await?;
 
         Ok(items)
@@ -120,13 +334,18 @@ impl Postgres {
         let query = sqlx::query_as!(
             CreateOrderDetailsEntity,
             r#"
-            INSERT INTO order_details (id, username, status, session_id, created_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO order_details (id, username, order_status, session_id, currency, subtotal, tax, shipping, total, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             "#,
             details.id,
             details.username,
-            details.status as _,
+            details.status as OrderStatusEntity,
             details.session_id,
+            details.currency as CurrencyEntity,
+            details.subtotal,
+            details.tax,
+            details.shipping,
+            details.total,
             details.created_at as DateTime<Utc>,
         );
         tx.execute(query).await?;
@@ -143,17 +362,21 @@ impl Postgres {
         let product_names: Vec<String> = items.iter().map(|item| item.product_name.clone()).collect();
         let item_ids: Vec<Uuid> = items.iter().map(|item| item.item_id).collect();
         let prices: Vec<Decimal> = items.iter().map(|item| item.price).collect();
+        let currencies: Vec<CurrencyEntity> = items.iter().map(|item| item.currency).collect();
+        let quantities: Vec<i32> = items.iter().map(|item| item.quantity).collect();
         let order_ids: Vec<Uuid> = items.iter().map(|item| item.order_id).collect();
         let query = sqlx::query_as!(
             CreateOrderItemEntity,
             r#"
-            INSERT INTO order_item (id, product_name, item_id, price, order_id)
-            SELECT * FROM UNNEST($1::Uuid[], $2::text[], $3::Uuid[], $4::Decimal[], $5::Uuid[])
+            INSERT INTO order_item (id, product_name, item_id, price, currency, quantity, order_id)
+            SELECT * FROM UNNEST($1::Uuid[], $2::text[], $3::Uuid[], $4::Decimal[], $5::currency[], $6::int4[], $7::Uuid[])
             "#,
             &ids,
             &product_names,
             &item_ids,
             &prices,
+            &currencies as &[CurrencyEntity],
+            &quantities,
             &order_ids,
         );
         tx.execute(query).await?;
@@ -161,13 +384,62 @@ impl Postgres {
         Ok(())
     }
 
-    async fn delete_orders(&self) -> Result<(), sqlx::Error> {
-        sqlx::query!("DELETE FROM order_details")
-            .execute(&self.pool)
+    /// Returns the deleted orders, including their items.
+    async fn delete_orders(
+        &self,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<(FetchOrderDetailsEntity, Vec<FetchOrderItemEntity>)>, sqlx::Error> {
+        // orders created meanwhile are left alone, locked ones can't get new items
+        let ids: Vec<Uuid> = sqlx::query_scalar!("SELECT id FROM order_details FOR UPDATE")
+            .fetch_all(&mut **tx)
+            .await?;
+        let items = sqlx::query_as!(
+            FetchOrderItemEntity,
+            r#"
+            SELECT id,
+                   product_name,
+                   item_id,
+                   price AS "price: Decimal",
+                   currency AS "currency: CurrencyEntity",
+                   quantity,
+                   order_id
+            FROM order_item
+            WHERE order_id = ANY($1)
+            "#,
+            &ids,
+        )
+            .fetch_all(&mut **tx)
+            .await?;
+        let details = sqlx::query_as!(
+            FetchOrderDetailsEntity,
+            r#"
+            DELETE FROM order_details
+            WHERE id = ANY($1)
+            RETURNING id,
+                      username,
+                      order_status AS "status: OrderStatusEntity",
+                      session_id,
+                      currency AS "currency: CurrencyEntity",
+                      subtotal AS "subtotal: Decimal",
+                      tax AS "tax: Decimal",
+                      shipping AS "shipping: Decimal",
+                      total AS "total: Decimal",
+                      created_at AS "created_at: DateTime<Utc>"
+            "#,
+            &ids,
+        )
+            .fetch_all(&mut **tx)
             .await?;
 
-        Ok(())
+        let mut items_by_order = group_by_order(items);
 
+        Ok(details
+            .into_iter()
+            .map(|details| {
+                let items = items_by_order.remove(&details.id).unwrap_or_default();
+                (details, items)
+            })
+            .collect())
     }
     
     async fn find_details_by_id(&self, id: &Uuid) -> Result<FetchOrderDetailsEntity, sqlx::Error> {
@@ -176,8 +448,13 @@ impl Postgres {
             r#"
             SELECT id,
                    username,
-                   status AS "status: SessionStatusEntity",
+                   order_status AS "status: OrderStatusEntity",
                    session_id,
+                   currency AS "currency: CurrencyEntity",
+                   subtotal AS "subtotal: Decimal",
+                   tax AS "tax: Decimal",
+                   shipping AS "shipping: Decimal",
+                   total AS "total: Decimal",
                    created_at AS "created_at: DateTime<Utc>"
             FROM order_details
             WHERE id = $1
@@ -192,7 +469,7 @@ impl Postgres {
     
     async fn process_details(&self, details: FetchOrderDetailsEntity) 
         -> Result<Order, FindOrderError> {
-        let items: Vec<FetchOrderItemEntity> = self.find_order_items_by_order_id(&details.id)
+        let items: Vec<FetchOrderItemEntity> = self.find_order_items_by_order_id(&details.id, &self.pool)
             .await
             .map_err(|e| {
                 FindOrderError::Unknown(anyhow!(e).context(format!(
@@ -200,6 +477,29 @@ impl Postgres {
                     ,details.id
                 )))
             })?;
+        Self::assemble_order(details, items)
+    }
+
+    /// Loads the items of all orders with one query, however many orders there are.
+    async fn process_all_details(&self, details: Vec<FetchOrderDetailsEntity>)
+        -> Result<Vec<Order>, FindOrderError> {
+        let order_ids: Vec<Uuid> = details.iter().map(|details| details.id).collect();
+        let items = self.find_order_items_by_order_ids(&order_ids)
+            .await
+            .context("Error finding order items")?;
+        let mut items_by_order = group_by_order(items);
+
+        details
+            .into_iter()
+            .map(|details| {
+                let items = items_by_order.remove(&details.id).unwrap_or_default();
+                Self::assemble_order(details, items)
+            })
+            .collect()
+    }
+
+    fn assemble_order(details: FetchOrderDetailsEntity, items: Vec<FetchOrderItemEntity>)
+        -> Result<Order, FindOrderError> {
         let details = details.into_domain();
         let items: Vec<OrderItem> = items
             .into_iter()
@@ -222,67 +522,227 @@ impl Postgres {
         Ok(order)
     }
 
+    /// Moves the order from `from` to `to`. Yields `None` if the order does not exist
+    /// or is no longer in `from`.
     async fn update_order_details_status(
         &self,
         id: &Uuid,
-        status: Option<SessionStatusEntity>,
-    ) -> Result<FetchOrderDetailsEntity, sqlx::Error> {
-        let updated_details = sqlx::query_as!(
+        from: OrderStatusEntity,
+        to: OrderStatusEntity,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<FetchOrderDetailsEntity>, sqlx::Error> {
+        sqlx::query_as!(
             FetchOrderDetailsEntity,
             r#"
             UPDATE order_details
-            SET status = $1
+            SET order_status = $1
             WHERE id = $2
-            RETURNING id, username, status as "status: SessionStatusEntity",
+              AND order_status = $3
+            RETURNING id, username, order_status as "status: OrderStatusEntity",
             session_id,
+            currency as "currency: CurrencyEntity",
+            subtotal as "subtotal: Decimal",
+            tax as "tax: Decimal",
+            shipping as "shipping: Decimal",
+            total as "total: Decimal",
             created_at as "created_at: DateTime<Utc>"
             "#,
-            status as Option<SessionStatusEntity>,
-            id
+            to as OrderStatusEntity,
+            id,
+            from as OrderStatusEntity,
         )
-            .fetch_one(&self.pool)
-            .await?;
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    async fn missing_update_error(&self, id: &Uuid) -> UpdateOrderError {
+        match self.find_details_by_id(id).await {
+            Ok(_) => UpdateOrderError::Conflict,
+            Err(sqlx::Error::RowNotFound) => UpdateOrderError::NotFound,
+            Err(e) => UpdateOrderError::Unknown(anyhow!(e).context(format!(
+                "Failed to find order details with id {id}"
+            ))),
+        }
+    }
+
+    /// Loads the order through `tx`, so it includes changes that are not committed yet.
+    async fn find_order_in_transaction(
+        &self,
+        details: FetchOrderDetailsEntity,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Order, FindOrderError> {
+        let items = self.find_order_items_by_order_id(&details.id, &mut **tx)
+            .await
+            .map_err(|e| {
+                FindOrderError::Unknown(anyhow!(e).context(format!(
+                    "Error finding order items for order id {}", details.id
+                )))
+            })?;
+
+        Self::assemble_order(details, items)
+    }
+
+    /// Stores `event` in the outbox, it is published once `tx` is committed.
+    async fn enqueue_event(
+        &self,
+        event: &OrderEvent,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), anyhow::Error> {
+        let message = OrderEventMessage::from_domain(event)?;
+        let query = sqlx::query!(
+            r#"
+            INSERT INTO outbox (id, order_id, event_type, payload, traceparent)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            message.event_id,
+            message.order_id,
+            event.kind().to_string(),
+            serde_json::to_value(&message)?,
+            TraceContext::current().map(|trace| trace.to_string()),
+        );
+        tx.execute(query).await?;
+
+        Ok(())
+    }
+
+    /// Records the change of the order to `to` and the event it causes, if any.
+    async fn record_transition(
+        &self,
+        order: &Order,
+        from: OrderStatus,
+        to: OrderStatus,
+        actor: &Actor,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), anyhow::Error> {
+        let id = order.details().order_id();
+        self.insert_status_change(id, Some(from.into()), Some(to.into()), actor, tx)
+            .await
+            .context("failed to record status change")?;
+
+        if let Some(kind) = OrderEventKind::for_status(to) {
+            let event = OrderEvent::new(Uuid::new_v4(), kind, order.clone(), Utc::now());
+            self.enqueue_event(&event, tx)
+                .await
+                .with_context(|| format!("failed to enqueue {kind} event for order {id}"))?;
+        }
+
+        Ok(())
+    }
+
+    async fn enqueue_deletion(
+        &self,
+        order: Order,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), anyhow::Error> {
+        let id = *order.details().order_id();
+        let event = OrderEvent::new(Uuid::new_v4(), OrderEventKind::Deleted, order, Utc::now());
+
+        self.enqueue_event(&event, tx)
+            .await
+            .with_context(|| format!("failed to enqueue deletion event for order {id}"))
+    }
+
+    async fn insert_status_change(
+        &self,
+        order_id: &Uuid,
+        previous_status: Option<OrderStatusEntity>,
+        new_status: Option<OrderStatusEntity>,
+        actor: &Actor,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let query = sqlx::query!(
+            r#"
+            INSERT INTO order_status_history (order_id, previous_status, new_status, actor)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            order_id,
+            previous_status as Option<OrderStatusEntity>,
+            new_status as Option<OrderStatusEntity>,
+            actor.to_string(),
+        );
+        tx.execute(query).await?;
+
+        Ok(())
+    }
+
+    async fn insert_processed_event(
+        &self,
+        event: &PaymentEvent,
+        order_id: &Uuid,
+        tx: &mut Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let query = sqlx::query!(
+            r#"
+            INSERT INTO processed_payment_events (event_id, order_id, kind)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (event_id) DO NOTHING
+            "#,
+            event.id().to_string(),
+            order_id,
+            event.kind().to_string(),
+        );
+        let result = tx.execute(query).await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+/// Bound as text, an array of the custom enum type would be looked up on every query.
+fn status_labels(filter: &OrderFilter) -> Vec<String> {
+    filter
+        .statuses()
+        .iter()
+        .map(|status| OrderStatusEntity::from(*status).label().to_string())
+        .collect()
+}
 
-        Ok(updated_details)
+fn group_by_order(items: Vec<FetchOrderItemEntity>) -> HashMap<Uuid, Vec<FetchOrderItemEntity>> {
+    let mut items_by_order: HashMap<Uuid, Vec<FetchOrderItemEntity>> = HashMap::new();
+    for item in items {
+        items_by_order.entry(item.order_id).or_default().push(item);
     }
+
+    items_by_order
 }
 
 impl OrderRepository for Postgres {
     
+    #[tracing::instrument(skip_all, fields(session_id = %req))]
     async fn find_order_by_session_id(&self, req: &SessionId) -> Result<Order, FindOrderError> {
         let details = self.find_details_by_session_id(req)
             .await
-            .map_err(|e| {
-                FindOrderError::Unknown(anyhow!(e).context(format!(
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => FindOrderError::SessionNotFound { session_id: req.clone() },
+                e => FindOrderError::Unknown(anyhow!(e).context(format!(
                     "Error finding order details by session {req}"
-                )))
+                ))),
             })?;
 
         self.process_details(details).await
     }
 
-    async fn find_orders_by_username(&self, req: &UserName) -> Result<Vec<Order>, FindOrderError> {
-        let mut orders: Vec<Order> = Vec::new();
-
-        let details_for_name = self.find_order_details_by_username(req)
+    #[tracing::instrument(skip_all, fields(limit = req.limit()))]
+    async fn list_orders(&self, req: &ListOrdersRequest) -> Result<OrderPage, FindOrderError> {
+        let mut details = self.find_order_details_page(req)
             .await
-            .map_err(|e|
-            FindOrderError::Unknown(anyhow!(e).context(format!(
-                "Error finding order details for username {req}"
-            ))))?;
+            .context("failed to find page of order details")?;
+        let total = self.count_orders(req)
+            .await
+            .context("failed to count orders")?;
 
-        for details in details_for_name {
-            let order = self.process_details(details).await?;
-            orders.push(order);
-        }
-        
-        Ok(orders)
+        let has_next_page = details.len() > *req.limit() as usize;
+        details.truncate(*req.limit() as usize);
+        let orders = self.process_all_details(details).await?;
+        let next_cursor = orders.last().filter(|_| has_next_page).map(OrderCursor::after);
 
+        Ok(OrderPage::new(orders, u64::try_from(total).unwrap_or_default(), next_cursor))
     }
 
+    #[tracing::instrument(skip_all, fields(order_id = %req.details().order_id()))]
     async fn create_order(&self, req: &Order) -> Result<Uuid, CreateOrderError> {
         let order_details = CreateOrderDetailsEntity::from_domain(req.details());
         let order_id = order_details.id.clone();
+        let status = order_details.status;
         let mut tx = self
             .pool
             .begin()
@@ -311,7 +771,15 @@ impl OrderRepository for Postgres {
             .await
             .context("failed to create order items")?;
 
+        let actor = Actor::User(req.details().username().clone());
+        self.insert_status_change(&order_id, None, Some(status), &actor, &mut tx)
+            .await
+            .context("failed to record order creation")?;
 
+        let event = OrderEvent::new(Uuid::new_v4(), OrderEventKind::Created, req.clone(), *req.details().created_at());
+        self.enqueue_event(&event, &mut tx)
+            .await
+            .with_context(|| format!("failed to enqueue creation event for order {order_id}"))?;
 
         tx.commit().await.context("failed to commit transaction")?;
 
@@ -319,30 +787,73 @@ impl OrderRepository for Postgres {
 
     }
 
-    async fn delete_order(&self, req: Uuid) -> Result<Uuid, DeleteOrderError> {
-        self.delete_order_by_id(req)
+    #[tracing::instrument(skip_all, fields(order_id = %req))]
+    async fn delete_order(&self, req: Uuid, actor: &Actor) -> Result<Uuid, DeleteOrderError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("failed to start Postgres transaction")?;
+
+        // the items are gone with the order, they are needed for the event
+        let items = self.find_order_items_by_order_id(&req, &mut *tx)
+            .await
+            .with_context(|| format!("failed to find items of order {req}"))?;
+        let deleted_details = self.delete_order_by_id(req, &mut tx)
             .await
             .map_err(|e| {
             DeleteOrderError::Unknown(anyhow!(e).context(format!(
                 "failed to delete order with ID {:?}", req.clone()
             )))
         })?;
+
+        if let Some(details) = deleted_details {
+            let status = details.status;
+            let order = Self::assemble_order(details, items)
+                .map_err(|e| DeleteOrderError::Unknown(e.into()))?;
+
+            self.insert_status_change(&req, Some(status), None, actor, &mut tx)
+                .await
+                .context("failed to record order deletion")?;
+            self.enqueue_deletion(order, &mut tx).await?;
+        }
+
+        tx.commit().await.context("failed to commit transaction")?;
+
         Ok(req)
     }
 
+    #[tracing::instrument(skip_all)]
     async fn delete_all_orders(&self) -> Result<(), DeleteOrderError> {
-        self.delete_orders().await.map_err(|e| {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("failed to start Postgres transaction")?;
+
+        let deleted = self.delete_orders(&mut tx).await.map_err(|e| {
             DeleteOrderError::Unknown(anyhow!(e).context("failed to delete all orders.".to_string()))
-        })
+        })?;
+        for (details, items) in deleted {
+            let order = Self::assemble_order(details, items)
+                .map_err(|e| DeleteOrderError::Unknown(e.into()))?;
+            self.enqueue_deletion(order, &mut tx).await?;
+        }
+
+        tx.commit().await.context("failed to commit transaction")?;
+
+        Ok(())
     }
 
+    #[tracing::instrument(skip_all, fields(order_id = %req))]
     async fn find_order_by_id(&self, req: Uuid) -> Result<Order, FindOrderError> {
         let details = self.find_details_by_id(&req)
             .await
-            .map_err(|e| {
-                FindOrderError::Unknown(anyhow!(e).context(format!(
-                    "Error finding order details by session {req}"
-                )))
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => FindOrderError::IdNotFound { id: req },
+                e => FindOrderError::Unknown(anyhow!(e).context(format!(
+                    "Error finding order details by id {req}"
+                ))),
             })?;
         
         
@@ -350,26 +861,242 @@ impl OrderRepository for Postgres {
         
     }
 
+    #[tracing::instrument(skip_all, fields(order_id = %id, from = %from, to = %to))]
     async fn update_order_status(
-        &self, id: &Uuid,
-        status: Option<&SessionStatus>
+        &self,
+        id: &Uuid,
+        from: &OrderStatus,
+        to: &OrderStatus,
+        actor: &Actor,
     ) -> Result<Order, UpdateOrderError> {
-        let status_entity = status.map(|s| SessionStatusEntity::from(s.clone()));
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("failed to start Postgres transaction")?;
+
+        let to_entity = OrderStatusEntity::from(*to);
         let updated_details = self
-            .update_order_details_status(id, status_entity)
+            .update_order_details_status(id, OrderStatusEntity::from(*from), to_entity, &mut tx)
             .await
             .map_err(|e| {
                 UpdateOrderError::Unknown(anyhow!(e).context(format!(
                     "Failed to update order details with id {id}"
                 )))
             })?;
+        let Some(updated_details) = updated_details else {
+            return Err(self.missing_update_error(id).await);
+        };
 
-        self.process_details(updated_details)
+        let order = self.find_order_in_transaction(updated_details, &mut tx)
             .await
             .map_err(|e| {
             UpdateOrderError::Unknown(anyhow!(e).context(format!(
                 "Failed to process order details with id {id}"
             )))
-        })
+        })?;
+        self.record_transition(&order, *from, *to, actor, &mut tx).await?;
+
+        tx.commit().await.context("failed to commit transaction")?;
+
+        Ok(order)
     }
-}
\ No newline at end of file
+
+    #[tracing::instrument(skip_all, fields(event_id = %event.id(), order_id = %id, to = %to))]
+    async fn apply_payment_event(
+        &self,
+        event: &PaymentEvent,
+        id: &Uuid,
+        from: &OrderStatus,
+        to: &OrderStatus,
+    ) -> Result<Option<Order>, UpdateOrderError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("failed to start Postgres transaction")?;
+
+        let first_delivery = self.insert_processed_event(event, id, &mut tx)
+            .await
+            .map_err(|e| {
+                UpdateOrderError::Unknown(anyhow!(e).context(format!(
+                    "Failed to record payment event {}", event.id()
+                )))
+            })?;
+        if !first_delivery {
+            return Ok(None);
+        }
+
+        let to_entity = OrderStatusEntity::from(*to);
+        let updated_details = self
+            .update_order_details_status(id, OrderStatusEntity::from(*from), to_entity, &mut tx)
+            .await
+            .map_err(|e| {
+                UpdateOrderError::Unknown(anyhow!(e).context(format!(
+                    "Failed to update order details with id {id}"
+                )))
+            })?;
+        let Some(updated_details) = updated_details else {
+            return Err(self.missing_update_error(id).await);
+        };
+
+        let order = self.find_order_in_transaction(updated_details, &mut tx)
+            .await
+            .map_err(|e| {
+                UpdateOrderError::Unknown(anyhow!(e).context(format!(
+                    "Failed to process order details with id {id}"
+                )))
+            })?;
+        self.record_transition(&order, *from, *to, &Actor::StripeWebhook, &mut tx).await?;
+
+        tx.commit().await.context("failed to commit transaction")?;
+
+        Ok(Some(order))
+    }
+
+    #[tracing::instrument(skip_all, fields(order_id = %id))]
+    async fn find_status_history(&self, id: &Uuid) -> Result<Vec<StatusChange>, FindOrderError> {
+        let history = sqlx::query_as!(
+            FetchStatusChangeEntity,
+            r#"
+            SELECT order_id,
+                   previous_status AS "previous_status: OrderStatusEntity",
+                   new_status AS "new_status: OrderStatusEntity",
+                   actor,
+                   changed_at AS "changed_at: DateTime<Utc>"
+            FROM order_status_history
+            WHERE order_id = $1
+            ORDER BY changed_at, id
+            "#,
+            id
+        )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                FindOrderError::Unknown(anyhow!(e).context(format!(
+                    "Error finding status history for order {id}"
+                )))
+            })?;
+
+        history.into_iter().map(FetchStatusChangeEntity::try_into_domain).collect()
+    }
+}
+
+impl HealthCheck for Postgres {
+    async fn check(&self) -> Result<(), HealthCheckError> {
+        self.ping().await.context("database did not answer")?;
+
+        Ok(())
+    }
+}
+
+impl OutboxRepository for Postgres {
+    async fn claim_pending(&self, limit: u32, lease: Duration) -> Result<Vec<OutboxMessage>, OutboxError> {
+        let messages = sqlx::query_as!(
+            FetchOutboxMessageEntity,
+            // RETURNING doesn't keep the order of the subquery, events of an order
+            // have to be published in the order they happened
+            r#"
+            WITH claimed AS (
+                UPDATE outbox
+                SET next_attempt_at = NOW() + make_interval(secs => $2)
+                WHERE id IN (
+                    SELECT id
+                    FROM outbox
+                    WHERE delivered_at IS NULL
+                      AND next_attempt_at <= NOW()
+                    ORDER BY created_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id,
+                          payload,
+                          attempts,
+                          traceparent,
+                          created_at
+            )
+            SELECT id,
+                   payload,
+                   attempts,
+                   traceparent
+            FROM claimed
+            ORDER BY created_at
+            "#,
+            i64::from(limit),
+            lease.as_secs_f64(),
+        )
+            .fetch_all(&self.pool)
+            .await
+            .context("failed to claim pending outbox messages")?;
+
+        messages
+            .into_iter()
+            .map(FetchOutboxMessageEntity::try_into_domain)
+            .collect()
+    }
+
+    async fn mark_delivered(&self, id: &Uuid) -> Result<(), OutboxError> {
+        sqlx::query!(
+            r#"
+            UPDATE outbox
+            SET delivered_at = NOW()
+            WHERE id = $1
+            "#,
+            id
+        )
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("failed to mark outbox message {id} as delivered"))?;
+
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: &Uuid, error: &str, retry_in: Duration) -> Result<(), OutboxError> {
+        sqlx::query!(
+            r#"
+            UPDATE outbox
+            SET attempts = attempts + 1,
+                last_error = $2,
+                next_attempt_at = NOW() + make_interval(secs => $3)
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            retry_in.as_secs_f64(),
+        )
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("failed to record delivery failure for outbox message {id}"))?;
+
+        Ok(())
+    }
+}
+
+impl ProductCatalog for Postgres {
+    async fn find_products(&self, ids: &[Uuid], currency: Currency) -> Result<Vec<Product>, ProductCatalogError> {
+        let products = sqlx::query_as!(
+            FetchProductEntity,
+            r#"
+            SELECT p.id,
+                   p.name,
+                   p.active,
+                   pp.price AS "price: Decimal",
+                   pp.currency AS "currency: CurrencyEntity"
+            FROM products p
+            JOIN product_prices pp ON pp.product_id = p.id
+            WHERE p.id = ANY($1)
+              AND pp.currency = $2
+            "#,
+            ids,
+            CurrencyEntity::from(currency) as CurrencyEntity,
+        )
+            .fetch_all(&self.pool)
+            .await
+            .context("failed to find products")?;
+
+        products
+            .into_iter()
+            .map(FetchProductEntity::try_into_domain)
+            .collect()
+    }
+}
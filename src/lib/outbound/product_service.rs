@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Context};
+use futures::future::try_join_all;
+use reqwest::{Client, StatusCode};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::domain::models::order_item::{Currency, Price, ProductName};
+use crate::domain::models::product::Product;
+use crate::domain::ports::product_catalog::{ProductCatalog, ProductCatalogError};
+use crate::telemetry::{TraceContext, TRACEPARENT};
+
+/// [`ProductCatalog`] backed by the product service's REST API.
+#[derive(Debug, Clone)]
+pub struct ProductServiceCatalog {
+    client: Client,
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProductResponse {
+    id: Uuid,
+    name: String,
+    active: bool,
+    prices: Vec<ProductPriceResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProductPriceResponse {
+    currency: String,
+    /// Decimal string in the major unit, e.g. `"19.99"`
+    amount: String,
+}
+
+impl ProductServiceCatalog {
+    #[must_use]
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn find_product(&self, id: &Uuid) -> Result<Option<ProductResponse>, ProductCatalogError> {
+        let url = format!("{}/api/products/{id}", self.base_url);
+        let mut request = self.client.get(&url);
+        if let Some(traceparent) = TraceContext::outgoing() {
+            request = request.header(TRACEPARENT, traceparent);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("failed to request product {id}"))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let product = response
+            .error_for_status()
+            .with_context(|| format!("product service failed to return product {id}"))?
+            .json()
+            .await
+            .with_context(|| format!("product service returned an invalid product {id}"))?;
+
+        Ok(Some(product))
+    }
+}
+
+impl ProductResponse {
+    fn into_domain(self, currency: Currency) -> Result<Option<Product>, ProductCatalogError> {
+        let Some(price) = self.prices
+            .iter()
+            .find(|price| price.currency.parse::<Currency>().is_ok_and(|c| c == currency))
+        else {
+            return Ok(None);
+        };
+
+        let amount = Decimal::from_str_exact(&price.amount)
+            .map_err(|e| anyhow!(e).context(format!("product {} has an invalid price", self.id)))?;
+        let price = Price::from_decimal(amount, currency)
+            .map_err(|e| anyhow!(e).context(format!("product {} has an invalid price", self.id)))?;
+
+        Ok(Some(Product::new(self.id, ProductName::new(&self.name), price, self.active)))
+    }
+}
+
+impl ProductCatalog for ProductServiceCatalog {
+    async fn find_products(&self, ids: &[Uuid], currency: Currency) -> Result<Vec<Product>, ProductCatalogError> {
+        let responses = try_join_all(ids.iter().map(|id| self.find_product(id))).await?;
+
+        let mut products = Vec::new();
+        for response in responses.into_iter().flatten() {
+            if let Some(product) = response.into_domain(currency)? {
+                products.push(product);
+            }
+        }
+
+        Ok(products)
+    }
+}
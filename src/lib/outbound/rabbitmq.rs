@@ -1,76 +1,561 @@
-use amqprs::BasicProperties;
-use amqprs::channel::{BasicPublishArguments, Channel, QueueDeclareArguments};
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use amqprs::{Ack, BasicProperties, Cancel, Close, CloseChannel, FieldTable, FieldValue, LongStr, Nack, Return};
+use amqprs::callbacks::{ChannelCallback, ConnectionCallback};
+use amqprs::channel::{BasicPublishArguments, Channel, ConfirmSelectArguments, ExchangeDeclareArguments, ExchangeType, QueueBindArguments, QueueDeclareArguments};
 use amqprs::connection::{Connection, OpenConnectionArguments};
-use serde::Serialize;
-use crate::domain::models::order_details::{SessionStatus, UserName};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use chrono::Utc;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use crate::domain::models::order_event::{OrderEvent, OrderEventKind};
 use crate::domain::ports::checkout_producer::{CheckoutProducer, NotifyError};
+use crate::domain::ports::health_check::{HealthCheck, HealthCheckError};
+use crate::metrics::METRICS;
+use crate::outbound::order_events::OrderEventMessage;
+use crate::telemetry::{TraceContext, REDACTED, TRACEPARENT};
 
+/// How long a publish waits for the broker to confirm it.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Publishes order events to `RabbitMQ`.
+///
+/// Publisher confirms are on, so a message only counts as sent once the broker has
+/// taken responsibility for it. The connection is opened again whenever it is found
+/// closed, e.g. after a broker restart.
 #[derive(Clone)]
 pub struct RabbitMQ {
+    config: Arc<RabbitMqConfig>,
+    /// Held for a whole publish, there is at most one unconfirmed message at a time.
+    connection: Arc<tokio::sync::Mutex<Option<Connected>>>,
+}
+
+/// The connection has to be kept around, dropping it closes the channel.
+struct Connected {
+    connection: Connection,
     channel: Channel,
-    exchange: String,
-    routing_key: String,
+    pending: Arc<Mutex<PendingConfirm>>,
+}
+
+impl Connected {
+    fn is_open(&self) -> bool {
+        self.connection.is_open() && self.channel.is_open()
+    }
+}
+
+/// What the broker said about a published message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Confirmation {
+    Ack,
+    Nack,
+    /// Acked after being returned because no queue was bound to its routing key.
+    Returned(String),
+}
+
+/// The publish waiting for its confirmation. Returns for mandatory messages arrive
+/// before their ack, so they are remembered until then.
+///
+/// The broker numbers the messages of a channel from 1 on, confirms for other
+/// messages than the awaited one, e.g. a late ack after a timeout, are ignored.
+#[derive(Debug, Default)]
+struct PendingConfirm {
+    /// Messages published on the channel, the last one is the awaited one.
+    published: u64,
+    returned: Option<String>,
+    sender: Option<oneshot::Sender<Confirmation>>,
+}
+
+impl PendingConfirm {
+    fn expect(&mut self) -> oneshot::Receiver<Confirmation> {
+        let (sender, receiver) = oneshot::channel();
+        self.published += 1;
+        self.returned = None;
+        self.sender = Some(sender);
+        receiver
+    }
+
+    fn returned(&mut self, reason: String) {
+        self.returned = Some(reason);
+    }
+
+    /// A return belongs to the message acked next, whether it's the awaited one or not.
+    fn acked(&mut self, delivery_tag: u64, multiple: bool) {
+        let returned = self.returned.take();
+        if self.confirms_awaited(delivery_tag, multiple) {
+            self.resolve(returned.map_or(Confirmation::Ack, Confirmation::Returned));
+        }
+    }
+
+    fn nacked(&mut self, delivery_tag: u64, multiple: bool) {
+        if self.confirms_awaited(delivery_tag, multiple) {
+            self.resolve(Confirmation::Nack);
+        }
+    }
+
+    const fn confirms_awaited(&self, delivery_tag: u64, multiple: bool) -> bool {
+        if multiple {
+            delivery_tag >= self.published
+        } else {
+            delivery_tag == self.published
+        }
+    }
+
+    /// The waiting publish sees its receiver closed.
+    fn abandon(&mut self) {
+        self.returned = None;
+        self.sender = None;
+    }
+
+    fn resolve(&mut self, confirmation: Confirmation) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(confirmation);
+        }
+    }
+}
+
+struct ConfirmCallback {
+    pending: Arc<Mutex<PendingConfirm>>,
+}
+
+impl ConfirmCallback {
+    fn pending(&self) -> std::sync::MutexGuard<'_, PendingConfirm> {
+        self.pending.lock().expect("confirm lock poisoned")
+    }
+}
+
+#[async_trait]
+impl ChannelCallback for ConfirmCallback {
+    async fn close(&mut self, _channel: &Channel, close: CloseChannel) -> Result<(), amqprs::error::Error> {
+        tracing::warn!("RabbitMQ closed the channel: {close}");
+        self.pending().abandon();
+        Ok(())
+    }
+
+    async fn cancel(&mut self, _channel: &Channel, _cancel: Cancel) -> Result<(), amqprs::error::Error> {
+        Ok(())
+    }
+
+    async fn flow(&mut self, _channel: &Channel, active: bool) -> Result<bool, amqprs::error::Error> {
+        Ok(active)
+    }
+
+    async fn publish_ack(&mut self, _channel: &Channel, ack: Ack) {
+        self.pending().acked(ack.delivery_tag(), ack.mutiple());
+    }
+
+    async fn publish_nack(&mut self, _channel: &Channel, nack: Nack) {
+        self.pending().nacked(nack.delivery_tag(), nack.multiple());
+    }
+
+    async fn publish_return(&mut self, _channel: &Channel, ret: Return, _properties: BasicProperties, _content: Vec<u8>) {
+        self.pending().returned(ret.to_string());
+    }
+}
+
+struct ConnectionLogger;
+
+#[async_trait]
+impl ConnectionCallback for ConnectionLogger {
+    async fn close(&mut self, _connection: &Connection, close: Close) -> Result<(), amqprs::error::Error> {
+        tracing::warn!("RabbitMQ closed the connection: {close}");
+        Ok(())
+    }
+
+    async fn blocked(&mut self, _connection: &Connection, reason: String) {
+        tracing::warn!("RabbitMQ blocked publishing: {reason}");
+    }
+
+    async fn unblocked(&mut self, _connection: &Connection) {
+        tracing::info!("RabbitMQ unblocked publishing");
+    }
+}
+
+/// Lets consumers continue the trace the event was published in.
+fn trace_headers() -> FieldTable {
+    let mut headers = FieldTable::new();
+    if let Some(traceparent) = TraceContext::outgoing() {
+        let value = LongStr::try_from(traceparent).expect("traceparent fits a long string");
+        headers.insert(TRACEPARENT.try_into().expect("valid field name"), FieldValue::S(value));
+    }
+
+    headers
+}
+
+/// Routing key of events of `kind`, e.g. `order.paid`.
+fn routing_key(prefix: &str, kind: OrderEventKind) -> String {
+    format!("{prefix}.{}", kind.short_name())
+}
+
+/// The broker and where order events are published to.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RabbitMqConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub exchange: String,
+    pub exchange_kind: ExchangeKind,
+    /// Order events are published with `{prefix}.{event}` as routing key, e.g. `order.paid`.
+    pub routing_key_prefix: String,
+    /// Declared and bound on startup so that no message is lost before the consumers are up.
+    pub bindings: Vec<QueueBinding>,
+}
+
+impl Debug for RabbitMqConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RabbitMqConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .field("exchange", &self.exchange)
+            .field("exchange_kind", &self.exchange_kind)
+            .field("routing_key_prefix", &self.routing_key_prefix)
+            .field("bindings", &self.bindings)
+            .finish()
+    }
+}
+
+/// How the exchange routes order events to the bound queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeKind {
+    /// Binding keys have to match the routing key exactly.
+    Direct,
+    /// Binding keys are patterns like `order.*` or `order.#`.
+    Topic,
+}
+
+impl FromStr for ExchangeKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind.to_ascii_lowercase().as_str() {
+            "direct" => Ok(Self::Direct),
+            "topic" => Ok(Self::Topic),
+            _ => Err(format!("unsupported exchange type {kind}, expected direct or topic")),
+        }
+    }
+}
+
+impl From<ExchangeKind> for ExchangeType {
+    fn from(kind: ExchangeKind) -> Self {
+        match kind {
+            ExchangeKind::Direct => Self::Direct,
+            ExchangeKind::Topic => Self::Topic,
+        }
+    }
+}
+
+/// A durable queue that receives the order events matching `binding_key`,
+/// written as `queue=binding_key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueBinding {
+    pub queue: String,
+    pub binding_key: String,
+}
+
+impl FromStr for QueueBinding {
+    type Err = String;
+
+    fn from_str(binding: &str) -> Result<Self, Self::Err> {
+        match binding.split_once('=') {
+            Some((queue, binding_key)) if !queue.trim().is_empty() && !binding_key.trim().is_empty() => Ok(Self {
+                queue: queue.trim().to_string(),
+                binding_key: binding_key.trim().to_string(),
+            }),
+            _ => Err(format!("expected queue=binding_key, found {binding}")),
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct CheckoutResult {
-    username: String,
-    status: String,
+#[derive(Debug, Error)]
+pub enum RabbitMqError {
+    #[error("failed to connect to {host}:{port}: {source}")]
+    Connect { host: String, port: u16, source: amqprs::error::Error },
+    #[error("failed to open a channel: {0}")]
+    OpenChannel(#[source] amqprs::error::Error),
+    #[error("failed to declare exchange {exchange}: {source}")]
+    DeclareExchange { exchange: String, source: amqprs::error::Error },
+    #[error("failed to declare or bind queue {queue}: {source}")]
+    DeclareQueue { queue: String, source: amqprs::error::Error },
+    #[error("failed to enable publisher confirms: {0}")]
+    ConfirmSelect(#[source] amqprs::error::Error),
 }
 
 impl RabbitMQ {
-    pub async fn new(host: &str, port: u16, routing_key: &str, exchange_name: &str) -> Self {
+    /// Doesn't connect yet, publishing fails with [`NotifyError::Unavailable`] until
+    /// the broker can be reached.
+    #[must_use]
+    pub fn new(config: RabbitMqConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            connection: Arc::new(tokio::sync::Mutex::new(None)),
+        }
+    }
+
+    /// Reconnects if the connection was lost, e.g. because the broker restarted.
+    ///
+    /// # Errors
+    /// [`RabbitMqError`] if the broker can't be reached or the channel can't be set up.
+    pub async fn ensure_connected(&self) -> Result<(), RabbitMqError> {
+        let mut connection = self.connection.lock().await;
+        self.connected(&mut connection).await.map(|_| ())
+    }
+
+    /// The open connection, a new one if there is none.
+    async fn connected<'a>(&self, connection: &'a mut Option<Connected>) -> Result<&'a Connected, RabbitMqError> {
+        if !connection.as_ref().is_some_and(Connected::is_open) {
+            *connection = None;
+            *connection = Some(self.connect().await?);
+        }
+
+        Ok(connection.as_ref().expect("connected above"))
+    }
+
+    async fn connect(&self) -> Result<Connected, RabbitMqError> {
+        let config = &self.config;
         let connection = Connection::open(
             &OpenConnectionArguments::new(
-                host,
-                port,
-                "admin",
-                "admin",
+                &config.host,
+                config.port,
+                &config.username,
+                &config.password,
             )
         )
             .await
-            .expect("Failed to open connection");
-
+            .map_err(|source| RabbitMqError::Connect {
+                host: config.host.clone(),
+                port: config.port,
+                source,
+            })?;
+        connection
+            .register_callback(ConnectionLogger)
+            .await
+            .map_err(|source| RabbitMqError::Connect {
+                host: config.host.clone(),
+                port: config.port,
+                source,
+            })?;
 
         let channel = connection.open_channel(None)
             .await
-            .expect("Failed to open channel");
+            .map_err(RabbitMqError::OpenChannel)?;
+        let pending = Arc::new(Mutex::new(PendingConfirm::default()));
+        channel
+            .register_callback(ConfirmCallback { pending: pending.clone() })
+            .await
+            .map_err(RabbitMqError::OpenChannel)?;
+        channel
+            .confirm_select(ConfirmSelectArguments::default())
+            .await
+            .map_err(RabbitMqError::ConfirmSelect)?;
 
-        let queue_name = "Checkout_ToBasket";
         channel
-            .queue_declare(QueueDeclareArguments::durable_client_named(queue_name))
+            .exchange_declare(
+                ExchangeDeclareArguments::of_type(&config.exchange, config.exchange_kind.into())
+                    .durable(true)
+                    .finish(),
+            )
             .await
-            .expect("Failed to declare queue");
+            .map_err(|source| RabbitMqError::DeclareExchange { exchange: config.exchange.clone(), source })?;
+        for binding in &config.bindings {
+            channel
+                .queue_declare(QueueDeclareArguments::durable_client_named(&binding.queue))
+                .await
+                .map_err(|source| RabbitMqError::DeclareQueue { queue: binding.queue.clone(), source })?;
+            channel
+                .queue_bind(QueueBindArguments::new(&binding.queue, &config.exchange, &binding.binding_key))
+                .await
+                .map_err(|source| RabbitMqError::DeclareQueue { queue: binding.queue.clone(), source })?;
+        }
 
-        Self {
-            channel,
-            exchange: exchange_name.to_string(),
-            routing_key: routing_key.to_string(),
+        tracing::info!("connected to RabbitMQ at {}:{}", config.host, config.port);
+
+        Ok(Connected { connection, channel, pending })
+    }
+
+    async fn publish(&self, event: &OrderEvent) -> Result<(), NotifyError> {
+        let message = OrderEventMessage::from_domain(event)?;
+        let payload = serde_json::to_vec(&message)
+            .context("failed to serialize order event")?;
+
+        let mut connection = self.connection.lock().await;
+        let connected = self.connected(&mut connection).await.map_err(|e| {
+            tracing::warn!("{e}");
+            NotifyError::Unavailable
+        })?;
+
+        let confirmation = connected.pending.lock().expect("confirm lock poisoned").expect();
+        let routing_key = routing_key(&self.config.routing_key_prefix, *event.kind());
+        let args = BasicPublishArguments::new(&self.config.exchange, &routing_key)
+            .mandatory(true)
+            .finish();
+        // redeliveries from the outbox keep the message id, so consumers can drop duplicates
+        let properties = BasicProperties::default()
+            .with_content_type("application/json")
+            .with_message_id(&event.id().to_string())
+            .with_message_type(&event.kind().to_string())
+            .with_timestamp(Utc::now().timestamp().try_into().unwrap_or_default())
+            .with_persistence(true)
+            .with_headers(trace_headers())
+            .finish();
+        let published = connected.channel
+            .basic_publish(properties, payload, args)
+            .await;
+        if let Err(e) = published {
+            // the broker may or may not have counted the message, the next delivery tag is unknown
+            *connection = None;
+            return Err(NotifyError::UnknownError(anyhow!(e).context("failed to publish order event")));
         }
+
+        let result = match tokio::time::timeout(CONFIRM_TIMEOUT, confirmation).await {
+            Ok(Ok(Confirmation::Ack)) => Ok(()),
+            Ok(Ok(Confirmation::Nack)) => Err(NotifyError::Rejected),
+            Ok(Ok(Confirmation::Returned(reason))) => Err(NotifyError::Unroutable(reason)),
+            Ok(Err(_)) => {
+                *connection = None;
+                Err(NotifyError::UnknownError(anyhow!("channel closed before the broker confirmed the message")))
+            }
+            Err(_) => {
+                connected.pending.lock().expect("confirm lock poisoned").abandon();
+                Err(NotifyError::UnknownError(anyhow!("broker did not confirm the message within {}s", CONFIRM_TIMEOUT.as_secs())))
+            }
+        };
+        // kept until the confirm, a channel that closed without one is reset above
+        drop(connection);
+
+        result
     }
 }
 
 impl CheckoutProducer for RabbitMQ {
-    async fn notify_order_result(&self,
-                                 username: &UserName,
-                                 status: &SessionStatus
-    ) -> Result<(), NotifyError>{
-        let result = CheckoutResult {
-            username: username.to_string(),
-            status: status.to_string(),
-        };
+    #[tracing::instrument(skip_all, fields(event_id = %event.id(), kind = %event.kind()))]
+    async fn publish_order_event(&self, event: &OrderEvent) -> Result<(), NotifyError> {
+        let result = self.publish(event).await;
 
-        let payload = serde_json::to_vec(&result).unwrap();
+        METRICS.rabbitmq_publish(match &result {
+            Ok(()) => "ack",
+            Err(NotifyError::Rejected) => "nack",
+            Err(NotifyError::Unroutable(_)) => "unroutable",
+            Err(NotifyError::Unavailable) => "unavailable",
+            Err(NotifyError::UnknownError(_)) => "error",
+        });
 
-        let args = BasicPublishArguments::new("","Checkout_ToBasket");
-        self.channel
-            .basic_publish(BasicProperties::default(),
-                           payload,
-                           args)
-            .await
-            .unwrap();
+        result
+    }
+}
 
-        Ok(())
+impl HealthCheck for RabbitMQ {
+    /// Only looks at the channel, reconnecting is left to [`ensure_connected`](Self::ensure_connected).
+    async fn check(&self) -> Result<(), HealthCheckError> {
+        // held by a publish waiting for its confirm, which needs an open channel
+        let Ok(connection) = self.connection.try_lock() else {
+            return Ok(());
+        };
+
+        match connection.as_ref() {
+            Some(connected) if connected.is_open() => Ok(()),
+            Some(_) => Err(HealthCheckError::Unavailable("channel is closed".to_string())),
+            None => Err(HealthCheckError::Unavailable("not connected".to_string())),
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::domain::models::order_event::OrderEventKind;
+    use crate::outbound::rabbitmq::{routing_key, Confirmation, PendingConfirm, QueueBinding};
+
+    #[test]
+    fn routing_key_per_event() {
+        assert_eq!(routing_key("order", OrderEventKind::Paid), "order.paid");
+        assert_eq!(routing_key("shop.order", OrderEventKind::Deleted), "shop.order.deleted");
+    }
+
+    #[test]
+    fn parse_queue_binding() {
+        let binding: QueueBinding = "Checkout_ToBasket = order.#".parse().unwrap();
+
+        assert_eq!(binding.queue, "Checkout_ToBasket");
+        assert_eq!(binding.binding_key, "order.#");
+        assert!("Checkout_ToBasket".parse::<QueueBinding>().is_err());
+        assert!("=order.paid".parse::<QueueBinding>().is_err());
+    }
+
+    #[test]
+    fn ack_confirms_publish() {
+        let mut pending = PendingConfirm::default();
+        let mut confirmation = pending.expect();
+
+        pending.acked(1, false);
+
+        assert_eq!(confirmation.try_recv().unwrap(), Confirmation::Ack);
+    }
+
+    #[test]
+    fn returned_message_is_unroutable_despite_ack() {
+        let mut pending = PendingConfirm::default();
+        let mut confirmation = pending.expect();
+
+        pending.returned("312: NO_ROUTE".to_string());
+        pending.acked(1, false);
+
+        assert_eq!(confirmation.try_recv().unwrap(), Confirmation::Returned("312: NO_ROUTE".to_string()));
+
+        let mut next = pending.expect();
+        pending.acked(2, false);
+        assert_eq!(next.try_recv().unwrap(), Confirmation::Ack);
+    }
+
+    #[test]
+    fn late_ack_after_abandon_does_not_confirm_next_publish() {
+        let mut pending = PendingConfirm::default();
+        let _timed_out = pending.expect();
+        pending.abandon();
+        let mut next = pending.expect();
+
+        pending.returned("312: NO_ROUTE".to_string());
+        pending.acked(1, false);
+
+        assert!(next.try_recv().is_err());
+
+        pending.acked(2, false);
+        assert_eq!(next.try_recv().unwrap(), Confirmation::Ack);
+    }
+
+    #[test]
+    fn multiple_ack_confirms_awaited_publish() {
+        let mut pending = PendingConfirm::default();
+        let _timed_out = pending.expect();
+        pending.abandon();
+        let mut next = pending.expect();
+
+        pending.nacked(1, false);
+        pending.acked(2, true);
+
+        assert_eq!(next.try_recv().unwrap(), Confirmation::Ack);
+    }
+
+    #[test]
+    fn nack_rejects_publish() {
+        let mut pending = PendingConfirm::default();
+        let mut confirmation = pending.expect();
+
+        pending.nacked(1, false);
+
+        assert_eq!(confirmation.try_recv().unwrap(), Confirmation::Nack);
+    }
+
+    #[test]
+    fn closed_channel_abandons_publish() {
+        let mut pending = PendingConfirm::default();
+        let mut confirmation = pending.expect();
+
+        pending.abandon();
+
+        assert!(confirmation.try_recv().is_err());
+    }
+}
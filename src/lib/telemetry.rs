@@ -0,0 +1,203 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::str::FromStr;
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+/// Logged in place of passwords, keys and tokens.
+pub const REDACTED: &str = "[redacted]";
+
+/// Header carrying the [`TraceContext`] of the caller, in HTTP as well as in AMQP messages.
+pub const TRACEPARENT: &str = "traceparent";
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+/// JSON lines on stdout, filtered by `RUST_LOG` and `info` by default. Records of
+/// dependencies that log through the `log` crate are included.
+pub fn init_logging() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    tracing_subscriber::fmt()
+        .json()
+        .flatten_event(true)
+        .with_env_filter(filter)
+        .init();
+}
+
+/// Where in a distributed trace we are, as defined by W3C Trace Context. Only
+/// propagated, spans are not exported anywhere.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    sampled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid traceparent: {0}")]
+pub struct ParseTraceContextError(String);
+
+impl TraceContext {
+    /// Starts a new trace.
+    #[must_use]
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: Uuid::new_v4().as_u128(),
+            span_id: new_span_id(),
+            sampled: true,
+        }
+    }
+
+    /// The context of an operation started from this one, same trace but a new span.
+    #[must_use]
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: new_span_id(),
+            ..*self
+        }
+    }
+
+    #[must_use]
+    pub fn trace_id(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    #[must_use]
+    pub fn span_id(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// The trace the current task is part of, `None` outside of [`in_trace`].
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|context| *context).ok()
+    }
+
+    /// The `traceparent` to send along with a call made from the current task.
+    #[must_use]
+    pub fn outgoing() -> Option<String> {
+        Self::current().map(|context| context.child().to_string())
+    }
+}
+
+/// Formats as a `traceparent` header value.
+impl Display for TraceContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00-{}-{}-{:02x}", self.trace_id(), self.span_id(), u8::from(self.sampled))
+    }
+}
+
+/// Parses a `traceparent` header value.
+impl FromStr for TraceContext {
+    type Err = ParseTraceContextError;
+
+    fn from_str(traceparent: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseTraceContextError(traceparent.to_string());
+        let hex = |field: &str, len: usize| {
+            (field.len() == len && field.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+                .then(|| u128::from_str_radix(field, 16).ok())
+                .flatten()
+                .ok_or_else(invalid)
+        };
+
+        let fields: Vec<&str> = traceparent.trim().split('-').collect();
+        let [version, trace_id, span_id, flags, rest @ ..] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        // later versions may append fields, version 00 must not
+        let version = hex(version, 2)?;
+        if version == 0xff || (version == 0 && !rest.is_empty()) {
+            return Err(invalid());
+        }
+
+        let trace_id = hex(trace_id, 32)?;
+        let span_id = u64::try_from(hex(span_id, 16)?).map_err(|_| invalid())?;
+        let flags = hex(flags, 2)?;
+        if trace_id == 0 || span_id == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self { trace_id, span_id, sampled: flags & 1 == 1 })
+    }
+}
+
+/// Random and never zero, which would be invalid.
+fn new_span_id() -> u64 {
+    Uuid::new_v4().as_u64_pair().0.max(1)
+}
+
+/// Runs `future` as part of the trace `context`.
+pub async fn in_trace<F: Future>(context: TraceContext, future: F) -> F::Output {
+    CURRENT.scope(context, future).await
+}
+
+/// Replaces the password in the user info of `url`, e.g. in a database url.
+#[must_use]
+pub fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rsplit_once('@') {
+        Some((user_info, host)) if user_info.contains(':') => {
+            let user = user_info.split(':').next().unwrap_or_default();
+            format!("{scheme}://{user}:{REDACTED}@{host}{}", &rest[authority_end..])
+        }
+        _ => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::telemetry::{in_trace, redact_url, TraceContext};
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn parses_and_formats_traceparent() {
+        let context: TraceContext = TRACEPARENT.parse().unwrap();
+
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.span_id(), "00f067aa0ba902b7");
+        assert_eq!(context.to_string(), TRACEPARENT);
+    }
+
+    #[test]
+    fn rejects_invalid_traceparent() {
+        for traceparent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert!(traceparent.parse::<TraceContext>().is_err(), "{traceparent}");
+        }
+    }
+
+    #[tokio::test]
+    async fn outgoing_calls_continue_the_current_trace() {
+        let context: TraceContext = TRACEPARENT.parse().unwrap();
+
+        let outgoing = in_trace(context, async { TraceContext::outgoing() }).await.unwrap();
+        let outgoing: TraceContext = outgoing.parse().unwrap();
+
+        assert_eq!(outgoing.trace_id(), context.trace_id());
+        assert_ne!(outgoing.span_id(), context.span_id());
+        assert!(TraceContext::outgoing().is_none());
+    }
+
+    #[test]
+    fn redacts_password_in_url() {
+        assert_eq!(
+            redact_url("postgres://admin:hunter2@db:5432/orders"),
+            "postgres://admin:[redacted]@db:5432/orders",
+        );
+        assert_eq!(redact_url("postgres://db:5432/orders"), "postgres://db:5432/orders");
+    }
+}
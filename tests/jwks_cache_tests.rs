@@ -0,0 +1,131 @@
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use jsonwebtoken::{decode, encode, Algorithm, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use bachelorarbeit::inbound::http::authorization::jwks::{JwksCache, JwksError};
+
+const ES256_PRIVATE_KEY: &[u8] = include_bytes!("fixtures/jwks/es256_private.pem");
+const ES256_X: &str = "edp2YUS7FEUQrSvbDXc-zugb0yetlN6Qd9zb2PiaVGY";
+const ES256_Y: &str = "EdLR-FMbyeXzdEmQ5J6Ik7YnQq6Pq1JQuurqREO309s";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: i64,
+}
+
+/// Stands in for Keycloak's certs endpoint, serving whatever key set the test put in.
+#[derive(Clone)]
+struct MockJwks {
+    jwks: Arc<Mutex<Value>>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockJwks {
+    fn start(jwks: Value) -> (Self, String) {
+        let mock = Self { jwks: Arc::new(Mutex::new(jwks)), requests: Arc::default() };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = mock.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .route("/certs", web::get().to(certs))
+        })
+            .listen(listener)
+            .unwrap()
+            .run();
+        tokio::spawn(server);
+
+        (mock, format!("http://127.0.0.1:{port}/certs"))
+    }
+
+    fn rotate_to(&self, jwks: Value) {
+        *self.jwks.lock().unwrap() = jwks;
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+async fn certs(mock: web::Data<MockJwks>) -> HttpResponse {
+    mock.requests.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "max-age=300"))
+        .json(mock.jwks.lock().unwrap().clone())
+}
+
+fn es256_key(kid: &str) -> Value {
+    json!({ "kty": "EC", "crv": "P-256", "alg": "ES256", "use": "sig", "kid": kid, "x": ES256_X, "y": ES256_Y })
+}
+
+fn key_set(keys: Vec<Value>) -> Value {
+    json!({ "keys": keys })
+}
+
+fn sign(kid: &str) -> String {
+    let header = Header { kid: Some(kid.to_string()), ..Header::new(Algorithm::ES256) };
+    let claims = Claims { sub: "Hannes".to_string(), exp: chrono::Utc::now().timestamp() + 60 };
+
+    encode(&header, &claims, &EncodingKey::from_ec_pem(ES256_PRIVATE_KEY).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_verifies_es256_token() {
+    let (_mock, uri) = MockJwks::start(key_set(vec![es256_key("ec-1")]));
+    let cache = JwksCache::new(&uri);
+
+    let key = cache.key("ec-1").await.unwrap();
+    let claims = decode::<Claims>(&sign("ec-1"), &key.key, &Validation::new(key.algorithm))
+        .unwrap()
+        .claims;
+
+    assert_eq!(key.algorithm, Algorithm::ES256);
+    assert_eq!(claims.sub, "Hannes");
+}
+
+#[tokio::test]
+async fn test_skips_keys_that_cannot_verify_signatures() {
+    let (_mock, uri) = MockJwks::start(key_set(vec![
+        es256_key("ec-1"),
+        json!({ "kty": "oct", "alg": "HS256", "kid": "shared", "k": "c2VjcmV0" }),
+        json!({ "kty": "EC", "crv": "P-256", "alg": "ES256", "use": "enc", "kid": "enc", "x": ES256_X, "y": ES256_Y }),
+    ]));
+    let cache = JwksCache::new(&uri);
+    cache.refresh().await.unwrap();
+
+    assert!(cache.key("ec-1").await.is_ok());
+    assert!(matches!(cache.key("shared").await, Err(JwksError::UnknownKeyId(_))));
+    assert!(matches!(cache.key("enc").await, Err(JwksError::UnknownKeyId(_))));
+}
+
+#[tokio::test]
+async fn test_unknown_kid_picks_up_rotated_key() {
+    let (mock, uri) = MockJwks::start(key_set(vec![es256_key("old")]));
+    let cache = JwksCache::new(&uri).with_min_refresh_interval(Duration::ZERO);
+    cache.refresh().await.unwrap();
+
+    mock.rotate_to(key_set(vec![es256_key("new")]));
+
+    assert!(cache.key("old").await.is_ok(), "cached keys are used until they expire");
+    assert!(cache.key("new").await.is_ok());
+    assert_eq!(mock.requests(), 2);
+}
+
+#[tokio::test]
+async fn test_unknown_kid_refreshes_are_rate_limited() {
+    let (mock, uri) = MockJwks::start(key_set(vec![es256_key("old")]));
+    let cache = JwksCache::new(&uri);
+    cache.refresh().await.unwrap();
+
+    for _ in 0..5 {
+        assert!(matches!(cache.key("made-up").await, Err(JwksError::UnknownKeyId(_))));
+    }
+
+    assert_eq!(mock.requests(), 1);
+}
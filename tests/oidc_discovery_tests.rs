@@ -0,0 +1,111 @@
+use std::net::TcpListener;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use jsonwebtoken::{decode, encode, Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use bachelorarbeit::inbound::http::authorization::jwks::JwksError;
+use bachelorarbeit::inbound::http::authorization::oidc::{DiscoveryError, OidcConfig};
+
+const ES256_PRIVATE_KEY: &[u8] = include_bytes!("fixtures/jwks/es256_private.pem");
+const ES256_X: &str = "edp2YUS7FEUQrSvbDXc-zugb0yetlN6Qd9zb2PiaVGY";
+const ES256_Y: &str = "EdLR-FMbyeXzdEmQ5J6Ik7YnQq6Pq1JQuurqREO309s";
+const KID: &str = "ec-1";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    iss: String,
+    aud: String,
+    exp: i64,
+}
+
+#[derive(Clone)]
+struct Provider {
+    issuer: String,
+    algorithms: Vec<&'static str>,
+}
+
+async fn openid_configuration(provider: web::Data<Provider>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "issuer": provider.issuer,
+        "jwks_uri": format!("{}/certs", provider.issuer),
+        "id_token_signing_alg_values_supported": provider.algorithms,
+    }))
+}
+
+async fn certs() -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "keys": [{ "kty": "EC", "crv": "P-256", "alg": "ES256", "use": "sig", "kid": KID, "x": ES256_X, "y": ES256_Y }]
+    }))
+}
+
+/// Stands in for an OpenID provider. `advertised_issuer` is what the discovery document
+/// claims, `None` for the URL it is served from.
+fn start_provider(advertised_issuer: Option<&str>, algorithms: Vec<&'static str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://127.0.0.1:{}/realms/shop", listener.local_addr().unwrap().port());
+    let provider = Provider {
+        issuer: advertised_issuer.map_or_else(|| url.clone(), ToString::to_string),
+        algorithms,
+    };
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(provider.clone()))
+            .route("/realms/shop/.well-known/openid-configuration", web::get().to(openid_configuration))
+            .route("/realms/shop/certs", web::get().to(certs))
+    })
+        .listen(listener)
+        .unwrap()
+        .run();
+    tokio::spawn(server);
+
+    url
+}
+
+fn config(issuer: &str) -> OidcConfig {
+    OidcConfig {
+        issuer: issuer.to_string(),
+        audience: vec!["payment".to_string()],
+        required_claims: vec!["exp".to_string(), "iss".to_string(), "aud".to_string()],
+    }
+}
+
+fn sign(issuer: &str, audience: &str) -> String {
+    let header = Header { kid: Some(KID.to_string()), ..Header::new(Algorithm::ES256) };
+    let claims = Claims {
+        iss: issuer.to_string(),
+        aud: audience.to_string(),
+        exp: chrono::Utc::now().timestamp() + 60,
+    };
+
+    encode(&header, &claims, &EncodingKey::from_ec_pem(ES256_PRIVATE_KEY).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_discovered_configuration_verifies_tokens() {
+    let issuer = start_provider(None, vec!["RS256", "ES256"]);
+    let (keys, mut validation) = config(&issuer).discover().await.unwrap();
+
+    let key = keys.key(KID).await.unwrap();
+    validation.algorithms = vec![key.algorithm];
+
+    assert!(decode::<Value>(&sign(&issuer, "payment"), &key.key, &validation).is_ok());
+    assert!(decode::<Value>(&sign(&issuer, "someone-else"), &key.key, &validation).is_err());
+    assert!(decode::<Value>(&sign("https://evil.example.com", "payment"), &key.key, &validation).is_err());
+}
+
+#[tokio::test]
+async fn test_rejects_configuration_of_another_issuer() {
+    let issuer = start_provider(Some("https://evil.example.com"), vec!["ES256"]);
+
+    let result = config(&issuer).discover().await;
+
+    assert!(matches!(result, Err(DiscoveryError::IssuerMismatch { .. })));
+}
+
+#[tokio::test]
+async fn test_only_keeps_keys_for_advertised_algorithms() {
+    let issuer = start_provider(None, vec!["RS256"]);
+    let (keys, _) = config(&issuer).discover().await.unwrap();
+
+    assert!(matches!(keys.key(KID).await, Err(JwksError::UnknownKeyId(_))));
+}
@@ -0,0 +1,106 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use bachelorarbeit::domain::models::order_details::SessionId;
+use bachelorarbeit::domain::models::payment_event::{PaymentEventId, PaymentEventKind};
+use bachelorarbeit::domain::ports::payment_service::{PaymentService, PaymentServiceError};
+use bachelorarbeit::domain::services::payment_service::StripeService;
+
+const WEBHOOK_SECRET: &str = "whsec_test_secret";
+
+const COMPLETED: &str = include_str!("fixtures/stripe/checkout_session_completed.json");
+const EXPIRED: &str = include_str!("fixtures/stripe/checkout_session_expired.json");
+const ASYNC_PAYMENT_FAILED: &str = include_str!("fixtures/stripe/checkout_session_async_payment_failed.json");
+const CUSTOMER_CREATED: &str = include_str!("fixtures/stripe/customer_created.json");
+
+fn stripe_service() -> StripeService {
+    StripeService::new(
+        "sk_test_unused".to_string(),
+        "http://localhost:8080".to_string(),
+        WEBHOOK_SECRET.to_string(),
+    )
+}
+
+/// Builds a `Stripe-Signature` header the same way Stripe does.
+fn sign(payload: &str, secret: &str) -> String {
+    let timestamp = Utc::now().timestamp();
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{timestamp}.{payload}").as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    format!("t={timestamp},v1={signature}")
+}
+
+#[test]
+fn test_parse_checkout_completed() {
+    let service = stripe_service();
+    let signature = sign(COMPLETED, WEBHOOK_SECRET);
+
+    let event = service
+        .parse_webhook_event(COMPLETED, &signature)
+        .unwrap()
+        .expect("checkout.session.completed should be handled");
+
+    assert_eq!(event.id(), &PaymentEventId::new("evt_1QXcompleted000000000001"));
+    assert_eq!(event.session_id(), &SessionId::new("cs_test_a1b2c3d4e5f6g7h8i9j0"));
+    assert_eq!(event.kind(), &PaymentEventKind::CheckoutCompleted { paid: true });
+}
+
+#[test]
+fn test_parse_checkout_expired() {
+    let service = stripe_service();
+    let signature = sign(EXPIRED, WEBHOOK_SECRET);
+
+    let event = service
+        .parse_webhook_event(EXPIRED, &signature)
+        .unwrap()
+        .expect("checkout.session.expired should be handled");
+
+    assert_eq!(event.kind(), &PaymentEventKind::CheckoutExpired);
+}
+
+#[test]
+fn test_parse_async_payment_failed() {
+    let service = stripe_service();
+    let signature = sign(ASYNC_PAYMENT_FAILED, WEBHOOK_SECRET);
+
+    let event = service
+        .parse_webhook_event(ASYNC_PAYMENT_FAILED, &signature)
+        .unwrap()
+        .expect("checkout.session.async_payment_failed should be handled");
+
+    assert_eq!(event.kind(), &PaymentEventKind::AsyncPaymentFailed);
+}
+
+#[test]
+fn test_unrelated_event_is_ignored() {
+    let service = stripe_service();
+    let signature = sign(CUSTOMER_CREATED, WEBHOOK_SECRET);
+
+    let event = service
+        .parse_webhook_event(CUSTOMER_CREATED, &signature)
+        .unwrap();
+
+    assert!(event.is_none());
+}
+
+#[test]
+fn test_wrong_secret_is_rejected() {
+    let service = stripe_service();
+    let signature = sign(COMPLETED, "whsec_someone_else");
+
+    let result = service.parse_webhook_event(COMPLETED, &signature);
+
+    assert!(matches!(result, Err(PaymentServiceError::InvalidWebhook(_))));
+}
+
+#[test]
+fn test_tampered_payload_is_rejected() {
+    let service = stripe_service();
+    let signature = sign(COMPLETED, WEBHOOK_SECRET);
+    let tampered = COMPLETED.replace("cs_test_a1b2c3d4e5f6g7h8i9j0", "cs_test_someone_elses_session");
+
+    let result = service.parse_webhook_event(&tampered, &signature);
+
+    assert!(matches!(result, Err(PaymentServiceError::InvalidWebhook(_))));
+}
@@ -0,0 +1,77 @@
+use std::net::TcpListener;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use serde_json::json;
+use uuid::{uuid, Uuid};
+use bachelorarbeit::domain::models::order_item::{Currency, Price};
+use bachelorarbeit::domain::ports::product_catalog::ProductCatalog;
+use bachelorarbeit::outbound::product_service::ProductServiceCatalog;
+
+const MONSTERA: Uuid = uuid!("6f1c1b8e-4a0e-4b8f-9d3a-1f2e3d4c5b6a");
+const RETIRED_CACTUS: Uuid = uuid!("0b9e2f4d-7c1a-4e6b-8a3d-5c2f1e0d9b8a");
+
+/// Stands in for the product service.
+async fn product(id: web::Path<Uuid>) -> HttpResponse {
+    match id.into_inner() {
+        MONSTERA => HttpResponse::Ok().json(json!({
+            "id": MONSTERA,
+            "name": "Monstera Deliciosa",
+            "active": true,
+            "prices": [
+                { "currency": "EUR", "amount": "19.99" },
+                { "currency": "USD", "amount": "21.50" }
+            ]
+        })),
+        RETIRED_CACTUS => HttpResponse::Ok().json(json!({
+            "id": RETIRED_CACTUS,
+            "name": "Cactus",
+            "active": false,
+            "prices": [{ "currency": "EUR", "amount": "4.00" }]
+        })),
+        _ => HttpResponse::NotFound().finish(),
+    }
+}
+
+fn start_stub() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = HttpServer::new(|| App::new().route("/api/products/{id}", web::get().to(product)))
+        .listen(listener)
+        .unwrap()
+        .run();
+    tokio::spawn(server);
+
+    format!("http://127.0.0.1:{port}/")
+}
+
+#[tokio::test]
+async fn test_finds_product_priced_in_currency() {
+    let catalog = ProductServiceCatalog::new(&start_stub());
+
+    let products = catalog.find_products(&[MONSTERA], Currency::Usd).await.unwrap();
+
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0].name().to_string(), "Monstera Deliciosa");
+    assert_eq!(products[0].price(), &Price::from_minor_units(2150, Currency::Usd).unwrap());
+    assert!(products[0].active());
+}
+
+#[tokio::test]
+async fn test_leaves_out_unknown_and_unpriced_products() {
+    let catalog = ProductServiceCatalog::new(&start_stub());
+
+    let products = catalog
+        .find_products(&[MONSTERA, Uuid::new_v4()], Currency::Jpy)
+        .await
+        .unwrap();
+
+    assert!(products.is_empty());
+}
+
+#[tokio::test]
+async fn test_reports_inactive_products() {
+    let catalog = ProductServiceCatalog::new(&start_stub());
+
+    let products = catalog.find_products(&[RETIRED_CACTUS], Currency::Eur).await.unwrap();
+
+    assert!(!products[0].active());
+}
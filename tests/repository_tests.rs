@@ -1,20 +1,34 @@
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use anyhow::Context;
-use chrono::Utc;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{ConnectOptions, PgPool};
 use sqlx::postgres::PgConnectOptions;
 use testcontainers_modules::postgres::Postgres as PostgreContainer;
 use testcontainers_modules::testcontainers::ContainerAsync;
 use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
 use uuid::Uuid;
-use bachelorarbeit::domain::models::order::{Order};
-use bachelorarbeit::domain::models::order_details::{OrderDetails, SessionId, SessionStatus, UserName};
-use bachelorarbeit::domain::models::order_item::{OrderItem, Price, ProductName};
+use bachelorarbeit::domain::models::order::{Order, UpdateOrderError};
+use bachelorarbeit::domain::models::order_details::{OrderDetails, OrderStatus, SessionId, UserName};
+use bachelorarbeit::domain::models::order_event::OrderEventKind;
+use bachelorarbeit::domain::models::order_history::Actor;
+use bachelorarbeit::domain::models::order_page::{ListOrdersRequest, OrderFilter, OrderPage, SortOrder};
+use bachelorarbeit::domain::models::order_item::{Currency, OrderItem, Price, ProductName, Quantity};
+use bachelorarbeit::domain::models::order_totals::OrderTotals;
+use bachelorarbeit::domain::models::payment_event::{PaymentEvent, PaymentEventId, PaymentEventKind};
 use bachelorarbeit::domain::ports::order_repository::OrderRepository;
-use bachelorarbeit::outbound::postgres::Postgres;
+use bachelorarbeit::domain::ports::outbox_repository::OutboxRepository;
+use bachelorarbeit::domain::ports::product_catalog::ProductCatalog;
+use bachelorarbeit::outbound::postgres::{Postgres, PostgresConfig};
 
 
-async fn setup_repository() -> (impl OrderRepository, ContainerAsync<PostgreContainer>) {
+async fn setup_repository() -> (Postgres, ContainerAsync<PostgreContainer>) {
     let container = PostgreContainer::default()
         .start()
         .await
@@ -34,28 +48,36 @@ async fn setup_repository() -> (impl OrderRepository, ContainerAsync<PostgreCont
     (repository, container)
 }
 
+fn actor() -> Actor {
+    Actor::User(UserName::new("Hannes"))
+}
+
     fn get_mock_create_order() -> Order {
-    let id = Uuid::default();
+    get_mock_order(Uuid::default(), "abc123", Utc::now())
+}
 
+fn get_mock_order(id: Uuid, session_id: &str, created_at: DateTime<Utc>) -> Order {
     let username = UserName::new("Hannes");
-    let session_status = Some(SessionStatus::Open);
-    let session_id = SessionId::new("abc123");
-    let created_at = Utc::now();
+    let session_status = OrderStatus::AwaitingPayment;
+    let session_id = SessionId::new(session_id);
     let details = OrderDetails::new(
         id,
         username,
         session_status,
         session_id,
+        Currency::Usd,
+        OrderTotals::new(Decimal::new(300, 2), Decimal::new(144, 2), Decimal::new(599, 2), Decimal::new(899, 2)),
         created_at,
     );
 
     let product_name = ProductName::new("Produkt");
-    let price = Price::new(1.0).unwrap();
+    let price = Price::from_minor_units(100, Currency::Usd).unwrap();
     let item = OrderItem::new(
         id,
         product_name,
         id,
-        price
+        price,
+        Quantity::new(3).unwrap(),
     );
 
     Order::new(
@@ -126,4 +148,300 @@ async fn test_create_order() {
     let order = get_mock_create_order();
     let id = repository.create_order(&order).await.unwrap();
     assert_eq!(id, Uuid::default());
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_find_order_keeps_quantity_and_currency() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+
+    let stored = repository.find_order_by_id(id).await.unwrap();
+
+    assert_eq!(stored.items()[0].quantity(), &Quantity::new(3).unwrap());
+    assert_eq!(stored.details().currency(), &Currency::Usd);
+    assert_eq!(stored.items()[0].price().currency(), &Currency::Usd);
+    assert_eq!(stored.totals(), order.totals());
+}
+
+#[tokio::test]
+async fn test_apply_payment_event_is_idempotent() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+    let event = PaymentEvent::new(
+        PaymentEventId::new("evt_123"),
+        order.details().session_id().clone(),
+        PaymentEventKind::CheckoutCompleted { paid: true },
+    );
+
+    let first = repository
+        .apply_payment_event(&event, &id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid)
+        .await
+        .unwrap();
+    let replay = repository
+        .apply_payment_event(&event, &id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid)
+        .await
+        .unwrap();
+
+    assert_eq!(first.unwrap().details().status(), &OrderStatus::Paid);
+    assert!(replay.is_none());
+}
+
+#[tokio::test]
+async fn test_apply_payment_event_rejects_stale_status() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+    let session_id = order.details().session_id().clone();
+    let expired = PaymentEvent::new(
+        PaymentEventId::new("evt_expired"),
+        session_id.clone(),
+        PaymentEventKind::CheckoutExpired,
+    );
+    let completed = PaymentEvent::new(
+        PaymentEventId::new("evt_completed"),
+        session_id,
+        PaymentEventKind::CheckoutCompleted { paid: true },
+    );
+
+    repository
+        .apply_payment_event(&expired, &id, &OrderStatus::AwaitingPayment, &OrderStatus::Expired)
+        .await
+        .unwrap();
+    let late = repository
+        .apply_payment_event(&completed, &id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid)
+        .await;
+    let stored = repository.find_order_by_id(id).await.unwrap();
+
+    assert!(matches!(late, Err(UpdateOrderError::Conflict)));
+    assert_eq!(stored.details().status(), &OrderStatus::Expired);
+}
+
+#[tokio::test]
+async fn test_update_order_status_enqueues_event() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+
+    repository
+        .update_order_status(&id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid, &actor())
+        .await
+        .unwrap();
+    let stale = repository
+        .update_order_status(&id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid, &actor())
+        .await;
+    let pending = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    let kinds: Vec<OrderEventKind> = pending.iter().map(|message| *message.event().kind()).collect();
+
+    assert!(matches!(stale, Err(UpdateOrderError::Conflict)));
+    assert_eq!(kinds, vec![OrderEventKind::Created, OrderEventKind::Paid]);
+    assert_eq!(pending[1].event().order().details().order_id(), &id);
+    assert_eq!(pending[1].event().order().details().status(), &OrderStatus::Paid);
+    assert_eq!(pending[1].event().order().items().len(), 1);
+}
+
+#[tokio::test]
+async fn test_delete_order_enqueues_event_with_items() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+
+    repository.delete_order(id, &actor()).await.unwrap();
+    let pending = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    let deleted = pending
+        .iter()
+        .find(|message| message.event().kind() == &OrderEventKind::Deleted)
+        .unwrap();
+
+    assert_eq!(deleted.event().order().details().order_id(), &id);
+    assert_eq!(deleted.event().order().items()[0].quantity(), &Quantity::new(3).unwrap());
+    assert_eq!(deleted.event().order().totals(), order.totals());
+}
+
+#[tokio::test]
+async fn test_list_orders_pages_through_filtered_orders() {
+    let (repository, _container) = setup_repository().await;
+    let start = Utc::now() - chrono::Duration::hours(1);
+    for i in 1..=3 {
+        let order = get_mock_order(Uuid::from_u128(i), &format!("cs_{i}"), start + chrono::Duration::minutes(i as i64));
+        repository.create_order(&order).await.unwrap();
+    }
+    repository
+        .update_order_status(&Uuid::from_u128(1), &OrderStatus::AwaitingPayment, &OrderStatus::Paid, &actor())
+        .await
+        .unwrap();
+    let request = |filter: OrderFilter, sort, after| {
+        ListOrdersRequest::new(UserName::new("Hannes"), filter, sort, Some(2), after).unwrap()
+    };
+    let ids = |page: &OrderPage| page
+        .orders()
+        .iter()
+        .map(|order| order.details().order_id().as_u128())
+        .collect::<Vec<_>>();
+
+    let first = repository.list_orders(&request(OrderFilter::default(), SortOrder::NewestFirst, None)).await.unwrap();
+    let second = repository.list_orders(&request(OrderFilter::default(), SortOrder::NewestFirst, *first.next_cursor())).await.unwrap();
+    let paid = OrderFilter::new(vec![OrderStatus::Paid], None, None).unwrap();
+    let paid = repository.list_orders(&request(paid, SortOrder::OldestFirst, None)).await.unwrap();
+    let between = OrderFilter::new(vec![], Some(start + chrono::Duration::minutes(2)), Some(start + chrono::Duration::minutes(3))).unwrap();
+    let between = repository.list_orders(&request(between, SortOrder::OldestFirst, None)).await.unwrap();
+
+    assert_eq!((ids(&first), *first.total()), (vec![3, 2], 3));
+    assert!(first.next_cursor().is_some());
+    assert_eq!((ids(&second), *second.total()), (vec![1], 3));
+    assert!(second.next_cursor().is_none());
+    assert_eq!((ids(&paid), *paid.total()), (vec![1], 1));
+    assert_eq!((ids(&between), *between.total()), (vec![2], 1));
+}
+
+/// Runs `future` and counts the statements sqlx executes meanwhile on this thread.
+async fn count_queries<T>(future: impl Future<Output = T>) -> (T, usize) {
+    #[derive(Clone, Default)]
+    struct QueryCounter(Arc<AtomicUsize>);
+
+    impl<S: tracing::Subscriber> Layer<S> for QueryCounter {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            if event.metadata().target() == "sqlx::query" {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    let counter = QueryCounter::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(counter.clone()));
+    let output = future.await;
+
+    (output, counter.0.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_listing_orders_takes_the_same_queries_for_any_number_of_orders() {
+    let (repository, _container) = setup_repository().await;
+    // sqlx looks up custom types once per connection, with a single one the warm up
+    // below does all lookups
+    let repository = Postgres::connect_lazy(&PostgresConfig {
+        url: repository.pool().connect_options().to_url_lossy().to_string(),
+        max_connections: 1,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(5),
+    }).unwrap();
+    let username = UserName::new("Hannes");
+    let page = ListOrdersRequest::new(username.clone(), OrderFilter::default(), SortOrder::NewestFirst, Some(100), None).unwrap();
+    repository.create_order(&get_mock_order(Uuid::from_u128(1), "cs_1", Utc::now())).await.unwrap();
+    repository.list_orders(&page).await.unwrap();
+
+    let (one_page, list_one) = count_queries(repository.list_orders(&page)).await;
+    for i in 2..=50 {
+        repository.create_order(&get_mock_order(Uuid::from_u128(i), &format!("cs_{i}"), Utc::now())).await.unwrap();
+    }
+    let (many_page, list_many) = count_queries(repository.list_orders(&page)).await;
+
+    assert_eq!(one_page.unwrap().orders().len(), 1);
+    assert_eq!(many_page.unwrap().orders().len(), 50);
+    // details, items and the total
+    assert_eq!((list_one, list_many), (3, 3));
+}
+
+#[tokio::test]
+async fn test_outbox_redelivers_until_marked_delivered() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+    repository
+        .update_order_status(&id, &OrderStatus::AwaitingPayment, &OrderStatus::Expired, &actor())
+        .await
+        .unwrap();
+
+    let claimed = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    let while_leased = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    repository.record_failure(claimed[0].id(), "broker down", Duration::ZERO).await.unwrap();
+    let retried = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    repository.mark_delivered(retried[0].id()).await.unwrap();
+    repository.record_failure(retried[0].id(), "lease ran out", Duration::ZERO).await.unwrap();
+    let after_delivery = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+
+    assert!(while_leased.is_empty());
+    assert_eq!(retried[0].attempts(), &1);
+    assert!(after_delivery.is_empty());
+}
+
+#[tokio::test]
+async fn test_outbox_claims_messages_in_the_order_they_were_created() {
+    let (repository, _container) = setup_repository().await;
+    let first = repository.create_order(&get_mock_order(Uuid::new_v4(), "first", Utc::now())).await.unwrap();
+    let second = repository.create_order(&get_mock_order(Uuid::new_v4(), "second", Utc::now())).await.unwrap();
+    repository
+        .update_order_status(&first, &OrderStatus::AwaitingPayment, &OrderStatus::Expired, &actor())
+        .await
+        .unwrap();
+    repository.delete_order(second, &actor()).await.unwrap();
+
+    let pending = repository.claim_pending(10, Duration::from_secs(30)).await.unwrap();
+    let claimed: Vec<(Uuid, OrderEventKind)> = pending
+        .iter()
+        .map(|message| (*message.event().order().details().order_id(), *message.event().kind()))
+        .collect();
+
+    assert_eq!(claimed, vec![
+        (first, OrderEventKind::Created),
+        (second, OrderEventKind::Created),
+        (first, OrderEventKind::Expired),
+        (second, OrderEventKind::Deleted),
+    ]);
+}
+
+#[tokio::test]
+async fn test_status_history_survives_deletion() {
+    let (repository, _container) = setup_repository().await;
+    let order = get_mock_create_order();
+    let id = repository.create_order(&order).await.unwrap();
+    let event = PaymentEvent::new(
+        PaymentEventId::new("evt_paid"),
+        order.details().session_id().clone(),
+        PaymentEventKind::CheckoutCompleted { paid: true },
+    );
+
+    repository
+        .apply_payment_event(&event, &id, &OrderStatus::AwaitingPayment, &OrderStatus::Paid)
+        .await
+        .unwrap();
+    repository.delete_order(id, &actor()).await.unwrap();
+    let history = repository.find_status_history(&id).await.unwrap();
+
+    let changes: Vec<_> = history
+        .iter()
+        .map(|change| (*change.previous_status(), *change.new_status(), change.actor().clone()))
+        .collect();
+    assert_eq!(changes, vec![
+        (None, Some(OrderStatus::AwaitingPayment), actor()),
+        (Some(OrderStatus::AwaitingPayment), Some(OrderStatus::Paid), Actor::StripeWebhook),
+        (Some(OrderStatus::Paid), None, actor()),
+    ]);
+}
+
+#[tokio::test]
+async fn test_product_catalog_prices_in_requested_currency() {
+    let (repository, _container) = setup_repository().await;
+    let monstera = Uuid::new_v4();
+    let cactus = Uuid::new_v4();
+    sqlx::query("INSERT INTO products (id, name, active) VALUES ($1, 'Monstera', TRUE), ($2, 'Cactus', FALSE)")
+        .bind(monstera)
+        .bind(cactus)
+        .execute(repository.pool())
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO product_prices (product_id, currency, price) VALUES ($1, 'EUR', 19.99), ($1, 'USD', 21.50), ($2, 'EUR', 4.00)")
+        .bind(monstera)
+        .bind(cactus)
+        .execute(repository.pool())
+        .await
+        .unwrap();
+
+    let in_usd = repository.find_products(&[monstera, cactus, Uuid::new_v4()], Currency::Usd).await.unwrap();
+    let in_eur = repository.find_products(&[cactus], Currency::Eur).await.unwrap();
+
+    assert_eq!(in_usd.len(), 1);
+    assert_eq!(in_usd[0].price(), &Price::from_minor_units(2150, Currency::Usd).unwrap());
+    assert!(!in_eur[0].active());
+}